@@ -26,4 +26,12 @@ mod tests {
         let varint = k256::PublicKey::multicodec_prefix_unsigned_varint();
         assert_eq!(varint, &[0xe7, 0x01])
     }
+
+    #[test]
+    fn split_prefix_k256() {
+        let bytes = [0xe7, 0x01, 0x02, 0x03];
+        let (prefix, rest) = split_multicodec_prefix(&bytes).unwrap();
+        assert_eq!(prefix, k256::PublicKey::multicodec_prefix_raw());
+        assert_eq!(rest, &[0x02, 0x03]);
+    }
 }
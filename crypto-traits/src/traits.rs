@@ -15,3 +15,12 @@ pub trait MulticodecPrefix {
         out_buf.to_vec()
     }
 }
+
+/// Splits bytes into a Multicodec prefix (decoded from the unsigned varint format) and the
+/// remaining bytes.
+///
+/// Returns `None` if the bytes don't start with a valid unsigned varint.
+/// The returned prefix can be compared against [MulticodecPrefix::multicodec_prefix_raw].
+pub fn split_multicodec_prefix(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    unsigned_varint::decode::u64(bytes).ok()
+}
@@ -1,5 +1,6 @@
+use std::hash::{Hash, Hasher};
+
 use crypto_traits::MulticodecPrefix;
-use derive_more::Into;
 use elliptic_curve::PublicKey;
 use k256::Secp256k1;
 use multibase::Base;
@@ -11,11 +12,21 @@ const DID_KEY_PREFIX: &str = "did:key:";
 
 /// A String newtype representing public key bytes in the did:key:<mb-value>
 /// format.
-#[derive(Into, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// The value is validated when parsed, and the decoded public key is kept alongside it
+/// (see [DidKey::public_key]).
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(into = "String", try_from = "String")]
 pub struct DidKey {
-    #[into]
     formatted_value: String,
+    public_key: DecodedPublicKey,
+}
+
+/// A public key decoded from a did:key, one variant per supported key type.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DecodedPublicKey {
+    Secp256k1(PublicKey<Secp256k1>),
+    NistP256(PublicKey<NistP256>),
 }
 
 impl From<PublicKey<Secp256k1>> for DidKey {
@@ -23,7 +34,11 @@ impl From<PublicKey<Secp256k1>> for DidKey {
         let multicodec_prefix = PublicKey::<Secp256k1>::multicodec_prefix_unsigned_varint();
         let key_bytes = public_key.to_sec1_bytes();
 
-        make_did_key(&multicodec_prefix, &key_bytes)
+        make_did_key(
+            &multicodec_prefix,
+            &key_bytes,
+            DecodedPublicKey::Secp256k1(public_key),
+        )
     }
 }
 
@@ -32,7 +47,41 @@ impl From<PublicKey<NistP256>> for DidKey {
         let multicodec_prefix = PublicKey::<NistP256>::multicodec_prefix_unsigned_varint();
         let key_bytes = public_key.to_sec1_bytes();
 
-        make_did_key(&multicodec_prefix, &key_bytes)
+        make_did_key(
+            &multicodec_prefix,
+            &key_bytes,
+            DecodedPublicKey::NistP256(public_key),
+        )
+    }
+}
+
+impl From<DecodedPublicKey> for DidKey {
+    fn from(public_key: DecodedPublicKey) -> Self {
+        match public_key {
+            DecodedPublicKey::Secp256k1(key) => key.into(),
+            DecodedPublicKey::NistP256(key) => key.into(),
+        }
+    }
+}
+
+impl From<DidKey> for String {
+    fn from(did_key: DidKey) -> Self {
+        did_key.formatted_value
+    }
+}
+
+// Equality and hashing only consider the formatted value
+impl PartialEq for DidKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.formatted_value == other.formatted_value
+    }
+}
+
+impl Eq for DidKey {}
+
+impl Hash for DidKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.formatted_value.hash(state);
     }
 }
 
@@ -42,26 +91,72 @@ pub enum Error {
     MissingPrefix,
     #[error("Invalid did:key multibase value")]
     InvalidValue,
+    #[error("Unsupported did:key multibase encoding (must be base58btc, was `{0:?}`)")]
+    UnsupportedEncoding(Base),
+    #[error("Missing or invalid multicodec prefix")]
+    InvalidMulticodecPrefix,
+    #[error("Unsupported key type (multicodec `{0:#x}`)")]
+    UnsupportedKeyType(u64),
+    #[error("Invalid public key bytes (not a valid SEC1 encoded point)")]
+    InvalidKeyBytes,
 }
 
 impl TryFrom<String> for DidKey {
     type Error = Error;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if !value.starts_with(DID_KEY_PREFIX) {
+        let Some(mb_value) = value.strip_prefix(DID_KEY_PREFIX) else {
             return Err(Error::MissingPrefix);
-        }
-        // TODO: Verify value
+        };
+        let public_key = decode_multibase_value(mb_value)?;
         Ok(DidKey {
             formatted_value: value,
+            public_key,
         })
     }
 }
 
+impl TryFrom<&str> for DidKey {
+    type Error = Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        DidKey::try_from(value.to_owned())
+    }
+}
+
+/// Decodes the multibase value of a did:key (without the "did:key:" prefix).
+///
+/// The value must be Base58Btc-encoded, start with a supported multicodec prefix,
+/// and contain a valid SEC1-encoded point for that key type.
+fn decode_multibase_value(mb_value: &str) -> Result<DecodedPublicKey, Error> {
+    let (base, bytes) = multibase::decode(mb_value).map_err(|_| Error::InvalidValue)?;
+    if base != Base::Base58Btc {
+        return Err(Error::UnsupportedEncoding(base));
+    }
+
+    let (prefix, key_bytes) =
+        crypto_traits::split_multicodec_prefix(&bytes).ok_or(Error::InvalidMulticodecPrefix)?;
+
+    if prefix == PublicKey::<Secp256k1>::multicodec_prefix_raw() {
+        PublicKey::<Secp256k1>::from_sec1_bytes(key_bytes)
+            .map(DecodedPublicKey::Secp256k1)
+            .map_err(|_| Error::InvalidKeyBytes)
+    } else if prefix == PublicKey::<NistP256>::multicodec_prefix_raw() {
+        PublicKey::<NistP256>::from_sec1_bytes(key_bytes)
+            .map(DecodedPublicKey::NistP256)
+            .map_err(|_| Error::InvalidKeyBytes)
+    } else {
+        Err(Error::UnsupportedKeyType(prefix))
+    }
+}
+
 /// Creates a did:key using a multicodec prefix + key bytes.
 /// The key is composed of a prefix (did:key:) and the Base58Btc-encoded value of those bytes.
 ///
 /// Described [here](https://w3c-ccg.github.io/did-method-key/#format).
-fn make_did_key(multicodec_prefix: &[u8], key_bytes: &[u8]) -> DidKey {
+fn make_did_key(
+    multicodec_prefix: &[u8],
+    key_bytes: &[u8],
+    public_key: DecodedPublicKey,
+) -> DidKey {
     let mb_value = multibase::encode(
         Base::Base58Btc,
         itertools::chain(multicodec_prefix, key_bytes)
@@ -71,6 +166,7 @@ fn make_did_key(multicodec_prefix: &[u8], key_bytes: &[u8]) -> DidKey {
 
     DidKey {
         formatted_value: format!("{DID_KEY_PREFIX}{mb_value}"),
+        public_key,
     }
 }
 
@@ -89,13 +185,18 @@ impl DidKey {
     pub fn formatted_value(&self) -> &str {
         &self.formatted_value
     }
+
+    /// The public key decoded from the multibase value
+    pub fn public_key(&self) -> &DecodedPublicKey {
+        &self.public_key
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use elliptic_curve::ScalarPrimitive;
 
-    use crate::did_key::DidKey;
+    use crate::did_key::{DecodedPublicKey, DidKey, Error};
 
     fn gen_did_key() -> DidKey {
         let secret_key = k256::SecretKey::new(ScalarPrimitive::from(1));
@@ -148,4 +249,80 @@ mod tests {
         assert!(err.is_data());
         // TODO: does not forward internal error enum!
     }
+
+    #[test]
+    fn decode_k256_key() {
+        let did_key = gen_did_key();
+
+        let decoded = DidKey::try_from(did_key.formatted_value()).unwrap();
+
+        assert_eq!(decoded.public_key(), did_key.public_key());
+        assert!(matches!(
+            decoded.public_key(),
+            DecodedPublicKey::Secp256k1(_)
+        ));
+    }
+
+    #[test]
+    fn decode_p256_key() {
+        let secret_key = p256::SecretKey::new(ScalarPrimitive::from(1));
+        let did_key: DidKey = secret_key.public_key().into();
+
+        let decoded = DidKey::try_from(did_key.formatted_value()).unwrap();
+
+        assert_eq!(
+            decoded.public_key(),
+            &DecodedPublicKey::NistP256(secret_key.public_key())
+        );
+    }
+
+    #[test]
+    fn reject_invalid_value() {
+        let err = DidKey::try_from("did:key:hello").expect_err("Accepted invalid value");
+        assert_eq!(err, Error::InvalidValue);
+    }
+
+    #[test]
+    fn reject_non_base58() {
+        let did_key = gen_did_key();
+        let (_, bytes) = multibase::decode(did_key.multibase_value()).unwrap();
+        let base32_value = multibase::encode(multibase::Base::Base32Lower, bytes);
+
+        let err = DidKey::try_from(format!("did:key:{base32_value}"))
+            .expect_err("Accepted non-base58btc value");
+        assert_eq!(
+            err,
+            Error::UnsupportedEncoding(multibase::Base::Base32Lower)
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_key_type() {
+        // Ed25519 (0xed) did:key
+        let err = DidKey::try_from("did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK")
+            .expect_err("Accepted unsupported key type");
+        assert_eq!(err, Error::UnsupportedKeyType(0xed));
+    }
+
+    #[test]
+    fn reject_truncated_key() {
+        let did_key = gen_did_key();
+        let (_, mut bytes) = multibase::decode(did_key.multibase_value()).unwrap();
+        bytes.pop();
+        let truncated = multibase::encode(multibase::Base::Base58Btc, bytes);
+
+        let err =
+            DidKey::try_from(format!("did:key:{truncated}")).expect_err("Accepted truncated key");
+        assert_eq!(err, Error::InvalidKeyBytes);
+    }
+
+    #[test]
+    fn deserialize_invalid_key() {
+        let invalid_str = r#""did:key:zQ3shVc2UkAfJCdc1TR8E66J85h48P43r93q8jGPkPpjF9Ef""#;
+
+        let err = serde_json::de::from_str::<DidKey>(invalid_str)
+            .expect_err("Failed to reject invalid key");
+
+        assert!(err.is_data());
+    }
 }
@@ -1,5 +1,3 @@
-#![feature(never_type)]
-
 mod did_key;
 
-pub use did_key::{DidKey, Error};
+pub use did_key::{DecodedPublicKey, DidKey, Error};
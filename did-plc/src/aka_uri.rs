@@ -1,40 +1,85 @@
-use derive_more::Into;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-const SUPPORTED_DID_METHODS: [&str; 2] = ["did:plc:", "did:web:"];
+use crate::did::{self, AtprotoDid, DID_PREFIX};
+use crate::handle;
+
 const AT_PREFIX: &str = "at://";
 
-#[derive(Debug, Clone, Serialize, Deserialize, Into)]
-pub struct AkaUri(#[into] String);
+/// An `alsoKnownAs` entry, i.e. an `at://` URI with a handle or DID authority.
+///
+/// The URI is kept exactly as parsed (so that signed operations serialize the same way),
+/// while [Self::authority] is normalized.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct AkaUri {
+    uri: String,
+    authority: AkaAuthority,
+}
+
+/// The authority of an [AkaUri]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AkaAuthority {
+    /// A handle, normalized to lowercase
+    Handle(String),
+    Did(AtprotoDid),
+}
 
 impl AkaUri {
-    /// Authority must be a DID (PLC or web) or a domain
+    /// Authority must be a DID (PLC or web) or a domain, handles are normalized to lowercase
     ///
     /// Examples:
     /// - `at://metaflame.dev`
     /// - `at://did:plc:c6te24qg5hx54qgegqylpqkx`
     /// - `at://did:web:example.com`
     pub fn new_at(authority: &str) -> Result<Self, Error> {
-        Self::try_from(format!("{AT_PREFIX}{}", authority))
+        let authority = parse_authority(authority)?;
+        let uri = format!("{AT_PREFIX}{}", authority.as_string());
+        Ok(Self { uri, authority })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn authority(&self) -> &AkaAuthority {
+        &self.authority
+    }
+
+    /// The handle, if the authority is one (lowercase)
+    pub fn handle(&self) -> Option<&str> {
+        match &self.authority {
+            AkaAuthority::Handle(handle) => Some(handle),
+            _ => None,
+        }
+    }
+}
+
+impl AkaAuthority {
+    /// The handle or formatted DID
+    pub fn as_string(&self) -> String {
+        match self {
+            AkaAuthority::Handle(handle) => handle.clone(),
+            AkaAuthority::Did(did) => did.formatted_did(),
+        }
     }
 }
 
-#[derive(Error, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
 pub enum Error {
     #[error(r#"Missing URI protocol (at://)"#)]
     MissingAtProtocol,
-
-    #[error(r#"Invalid at:// authority (must be one of: handle, did:plc, did:web)"#)]
-    InvalidAuthority, // Use soft validation instead?
+    #[error("Invalid at:// handle: {0}")]
+    InvalidHandle(#[from] handle::Error),
+    #[error("Invalid at:// DID: {0}")]
+    InvalidDid(#[from] did::Error),
 }
 
 impl TryFrom<&str> for AkaUri {
     type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        validate(value)?;
-        Ok(Self(value.to_owned()))
+        Self::try_from(value.to_owned())
     }
 }
 
@@ -42,54 +87,54 @@ impl TryFrom<String> for AkaUri {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        validate(&value)?;
-        Ok(Self(value))
+        let authority = value
+            .strip_prefix(AT_PREFIX)
+            .ok_or(Error::MissingAtProtocol)?;
+        Ok(Self {
+            authority: parse_authority(authority)?,
+            uri: value,
+        })
     }
 }
 
-fn validate(value: &str) -> Result<(), Error> {
-    if !value.starts_with(AT_PREFIX) {
-        return Err(Error::MissingAtProtocol);
+impl From<AkaUri> for String {
+    fn from(value: AkaUri) -> Self {
+        value.uri
     }
+}
 
-    let authority = &value[AT_PREFIX.len()..];
-
-    // Authority must be either a valid handle, or start with one of the supported did methods
-    // TODO: better did method validation?
-    if crate::handle::validate_handle(authority).is_err()
-        && !SUPPORTED_DID_METHODS
-            .iter()
-            .any(|prefix| authority.starts_with(prefix))
-    {
-        return Err(Error::InvalidAuthority);
+fn parse_authority(authority: &str) -> Result<AkaAuthority, Error> {
+    if authority.starts_with(DID_PREFIX) {
+        return Ok(AkaAuthority::Did(AtprotoDid::try_from(authority)?));
     }
-
-    Ok(())
+    handle::validate_handle(authority)?;
+    Ok(AkaAuthority::Handle(authority.to_ascii_lowercase()))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::aka_uri::{AkaUri, Error};
+    use crate::aka_uri::{AkaAuthority, AkaUri, Error};
+    use crate::{did, did_plc, handle, AtprotoDid};
 
     #[test]
     fn from_handle_uri() {
         let input = "at://metaflame.dev";
         let aka_uri: AkaUri = input.try_into().expect("Failed to parse");
-        assert_eq!(aka_uri.0, input);
+        assert_eq!(aka_uri.as_str(), input);
     }
 
     #[test]
     fn from_did_plc() {
         let input = "at://did:plc:c6te24qg5hx54qgegqylpqkx";
         let aka_uri: AkaUri = input.try_into().expect("Failed to parse");
-        assert_eq!(aka_uri.0, input);
+        assert_eq!(aka_uri.as_str(), input);
     }
 
     #[test]
     fn from_did_web() {
         let input = "at://did:web:example.com";
         let aka_uri: AkaUri = input.try_into().expect("Failed to parse");
-        assert_eq!(aka_uri.0, input);
+        assert_eq!(aka_uri.as_str(), input);
     }
 
     #[test]
@@ -97,7 +142,7 @@ mod tests {
         let input = "at://invalid";
         let result: Result<AkaUri, _> = input.try_into();
         let err = result.expect_err("Parsed unsupported authority");
-        assert_eq!(err, Error::InvalidAuthority);
+        assert_eq!(err, Error::InvalidHandle(handle::Error::OnlyOneSegment));
     }
 
     #[test]
@@ -107,4 +152,46 @@ mod tests {
         let err = result.expect_err("Parsed without at://");
         assert_eq!(err, Error::MissingAtProtocol);
     }
+
+    #[test]
+    fn typed_authority() {
+        let handle = AkaUri::try_from("at://Metaflame.DEV").unwrap();
+        assert_eq!(handle.as_str(), "at://Metaflame.DEV");
+        assert_eq!(handle.handle(), Some("metaflame.dev"));
+
+        let did_plc = AkaUri::try_from("at://did:plc:c6te24qg5hx54qgegqylpqkx").unwrap();
+        assert!(matches!(
+            did_plc.authority(),
+            AkaAuthority::Did(AtprotoDid::Plc(_))
+        ));
+        assert_eq!(did_plc.handle(), None);
+
+        let did_web = AkaUri::try_from("at://did:web:example.com%3A8080").unwrap();
+        let AkaAuthority::Did(AtprotoDid::Web(did_web)) = did_web.authority() else {
+            panic!("Expected did:web authority");
+        };
+        assert_eq!(did_web.port(), Some(8080));
+    }
+
+    #[test]
+    fn new_at_normalizes_handle() {
+        let aka_uri = AkaUri::new_at("Metaflame.dev").unwrap();
+        assert_eq!(aka_uri.as_str(), "at://metaflame.dev");
+    }
+
+    #[test]
+    fn invalid_did_authority() {
+        assert_eq!(
+            AkaUri::try_from("at://did:plc:c6te24qg5hx54qgegqyl0189"),
+            Err(Error::InvalidDid(did::Error::InvalidDidPlc(
+                did_plc::Error::InvalidHash
+            )))
+        );
+        assert_eq!(
+            AkaUri::try_from("at://did:key:zQ3sh"),
+            Err(Error::InvalidDid(did::Error::UnsupportedMethod(
+                "did:key:zQ3sh".to_string()
+            )))
+        );
+    }
 }
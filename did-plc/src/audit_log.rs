@@ -0,0 +1,333 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use derive_more::{Deref, From, Into};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::operation::{SignedOperation, VerificationError};
+use crate::{plc_operation_ref, DidPlc, PlcOperationRef};
+
+/// Time during which a higher-priority rotation key may override (nullify) an operation
+/// signed by a lower-priority key.
+pub const RECOVERY_WINDOW: TimeDelta = TimeDelta::hours(72);
+
+/// A single entry of a did:plc audit log (as returned by `/log/audit`).
+///
+/// Field order matches the order used by [plc.directory](https://plc.directory).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntry {
+    pub did: DidPlc,
+    pub operation: SignedOperation,
+    pub cid: PlcOperationRef,
+    pub nullified: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogEntry {
+    /// Until when this operation can be nullified by a higher-priority rotation key.
+    pub fn recovery_window_closes(&self) -> DateTime<Utc> {
+        self.created_at + RECOVERY_WINDOW
+    }
+}
+
+/// An ordered audit log of a single did:plc, including nullified operations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Deref, From, Into)]
+#[serde(transparent)]
+pub struct AuditLog(Vec<AuditLogEntry>);
+
+/// Outcome of [AuditLog::resolve] for an accepted operation.
+///
+/// All indices refer to positions in the audit log.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Resolution {
+    /// Index of the operation referenced by `prev` (`None` for a genesis operation)
+    pub prev_index: Option<usize>,
+    /// Indices of the (currently active) operations which the proposed operation nullifies
+    pub nullified: Vec<usize>,
+    /// Index of the rotation key (of the `prev` operation) which signed the proposed operation
+    pub signing_key_index: usize,
+    /// Until when the proposed operation itself can be overridden by a higher-priority key
+    pub recovery_window_closes: DateTime<Utc>,
+}
+
+impl Resolution {
+    pub fn is_fork(&self) -> bool {
+        !self.nullified.is_empty()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ResolutionError {
+    #[error("Genesis operation proposed, but the log already has operations")]
+    UnexpectedGenesis,
+    #[error("The `prev` operation (`{0}`) is not an active operation in the log")]
+    PrevNotFound(Box<PlcOperationRef>),
+    #[error("The `prev` operation is a tombstone")]
+    PrevIsTombstone,
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(#[from] VerificationError),
+    #[error(
+        "Signing key (index {signing_key_index}) does not have a higher priority than the key \
+        which signed the overridden operation (index {disputed_key_index})"
+    )]
+    InsufficientKeyPriority {
+        signing_key_index: usize,
+        disputed_key_index: usize,
+    },
+    #[error("The recovery window closed at {closed_at}")]
+    RecoveryWindowClosed { closed_at: DateTime<Utc> },
+    #[error(transparent)]
+    Cid(#[from] plc_operation_ref::Error),
+}
+
+impl AuditLog {
+    pub fn new(entries: Vec<AuditLogEntry>) -> Self {
+        Self(entries)
+    }
+
+    /// Iterates over operations which are not nullified, along with their audit log index.
+    pub fn active_entries(&self) -> impl Iterator<Item = (usize, &AuditLogEntry)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.nullified)
+    }
+
+    /// The latest operation which is not nullified.
+    pub fn last_active(&self) -> Option<&AuditLogEntry> {
+        self.0.iter().rev().find(|entry| !entry.nullified)
+    }
+
+    /// Decides whether `proposed` would be accepted at time `now`, following the
+    /// [plc.directory](https://web.plc.directory/spec/v0.1/did-plc) rules:
+    /// - a genesis operation is only accepted for an empty log
+    /// - `prev` must reference an active (non-nullified) operation, which isn't a tombstone
+    /// - the signature must come from a rotation key of the `prev` operation
+    /// - if `prev` isn't the latest active operation, all later active operations are
+    ///   nullified. This is only allowed if the proposed operation is signed by a key with a
+    ///   higher priority (lower index) than the key which signed the first nullified
+    ///   operation, and only within [RECOVERY_WINDOW] of that operation's creation.
+    pub fn resolve(
+        &self,
+        proposed: &SignedOperation,
+        now: DateTime<Utc>,
+    ) -> Result<Resolution, ResolutionError> {
+        let recovery_window_closes = now + RECOVERY_WINDOW;
+
+        let Some(prev) = proposed.prev() else {
+            if self.active_entries().next().is_some() {
+                return Err(ResolutionError::UnexpectedGenesis);
+            }
+            let signing_key_index = proposed.verify(proposed.rotation_keys())?;
+            return Ok(Resolution {
+                prev_index: None,
+                nullified: vec![],
+                signing_key_index,
+                recovery_window_closes,
+            });
+        };
+
+        let (prev_index, prev_entry) =
+            self.active_entries()
+                .find(|(_, entry)| entry.cid == prev)
+                .ok_or_else(|| ResolutionError::PrevNotFound(Box::new(prev)))?;
+        if prev_entry.operation.is_tombstone() {
+            return Err(ResolutionError::PrevIsTombstone);
+        }
+        let rotation_keys = prev_entry.operation.rotation_keys();
+
+        let signing_key_index = proposed.verify(rotation_keys)?;
+
+        let nullified: Vec<usize> = self
+            .active_entries()
+            .map(|(index, _)| index)
+            .filter(|index| *index > prev_index)
+            .collect();
+
+        if let Some(first_nullified) = nullified.first().map(|index| &self.0[*index]) {
+            let disputed_key_index = first_nullified.operation.verify(rotation_keys)?;
+            if signing_key_index >= disputed_key_index {
+                return Err(ResolutionError::InsufficientKeyPriority {
+                    signing_key_index,
+                    disputed_key_index,
+                });
+            }
+
+            let closed_at = first_nullified.recovery_window_closes();
+            if now > closed_at {
+                return Err(ResolutionError::RecoveryWindowClosed { closed_at });
+            }
+        }
+
+        Ok(Resolution {
+            prev_index: Some(prev_index),
+            nullified,
+            signing_key_index,
+            recovery_window_closes,
+        })
+    }
+
+    /// Resolves `proposed` (see [AuditLog::resolve]), and if accepted, marks the overridden
+    /// operations as nullified and appends the operation to the log.
+    pub fn apply(
+        &mut self,
+        did: &DidPlc,
+        proposed: SignedOperation,
+        now: DateTime<Utc>,
+    ) -> Result<Resolution, ResolutionError> {
+        let resolution = self.resolve(&proposed, now)?;
+        let cid = proposed.get_cid_reference()?;
+
+        for index in &resolution.nullified {
+            self.0[*index].nullified = true;
+        }
+        self.0.push(AuditLogEntry {
+            did: did.clone(),
+            operation: proposed,
+            cid,
+            nullified: false,
+            created_at: now,
+        });
+
+        Ok(resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::test_util::{did_key, fork_fixture, signed_op};
+    use crate::UnsignedPlcTombstone;
+
+    #[test]
+    fn append_to_last() {
+        let f = fork_fixture();
+        let last_ref = f.log.last_active().unwrap().cid;
+        let op = signed_op(&[], Some(last_ref), &f.keys[2]);
+
+        let resolution = f.log.resolve(&op, f.created_at).unwrap();
+        assert_eq!(resolution.prev_index, Some(1));
+        assert_eq!(resolution.signing_key_index, 0);
+        assert!(!resolution.is_fork());
+    }
+
+    #[test]
+    fn reject_second_genesis() {
+        let f = fork_fixture();
+        let op = signed_op(&[did_key(&f.keys[0])], None, &f.keys[0]);
+
+        assert_matches!(
+            f.log.resolve(&op, f.created_at),
+            Err(ResolutionError::UnexpectedGenesis)
+        );
+    }
+
+    #[test]
+    fn recover_with_higher_priority_key() {
+        let mut f = fork_fixture();
+        let op = signed_op(&[did_key(&f.keys[0])], Some(f.genesis_ref), &f.keys[1]);
+        let now = f.created_at + TimeDelta::hours(71);
+
+        let did = f.log[0].did.clone();
+        let resolution = f.log.apply(&did, op, now).unwrap();
+        assert_eq!(resolution.nullified, vec![1]);
+        assert_eq!(resolution.signing_key_index, 1);
+        assert!(f.log[1].nullified);
+        assert_eq!(f.log.last_active().unwrap().created_at, now);
+    }
+
+    #[test]
+    fn reject_equal_priority_key() {
+        let f = fork_fixture();
+        let op = signed_op(&[], Some(f.genesis_ref), &f.keys[2]);
+
+        assert_matches!(
+            f.log.resolve(&op, f.created_at),
+            Err(ResolutionError::InsufficientKeyPriority {
+                signing_key_index: 2,
+                disputed_key_index: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn reject_late_recovery() {
+        let f = fork_fixture();
+        let op = signed_op(&[], Some(f.genesis_ref), &f.keys[0]);
+        let now = f.created_at + RECOVERY_WINDOW + TimeDelta::seconds(1);
+
+        assert_matches!(
+            f.log.resolve(&op, now),
+            Err(ResolutionError::RecoveryWindowClosed { .. })
+        );
+    }
+
+    #[test]
+    fn reject_unknown_prev() {
+        let f = fork_fixture();
+        let unknown_ref = signed_op(&[], None, &f.keys[0])
+            .get_cid_reference()
+            .unwrap();
+        let op = signed_op(&[], Some(unknown_ref), &f.keys[0]);
+
+        assert_matches!(
+            f.log.resolve(&op, f.created_at),
+            Err(ResolutionError::PrevNotFound(_))
+        );
+    }
+
+    #[test]
+    fn reject_after_tombstone() {
+        let mut f = fork_fixture();
+        let did = f.log[0].did.clone();
+        let last_ref = f.log.last_active().unwrap().cid;
+        let tombstone = UnsignedPlcTombstone::new(last_ref).sign(&f.keys[2]);
+        f.log.apply(&did, tombstone.into(), f.created_at).unwrap();
+
+        let tombstone_ref = f.log.last_active().unwrap().cid;
+        let op = signed_op(&[], Some(tombstone_ref), &f.keys[2]);
+        assert_matches!(
+            f.log.resolve(&op, f.created_at),
+            Err(ResolutionError::PrevIsTombstone)
+        );
+    }
+
+    #[test]
+    fn audit_entry_serde() {
+        let entry_json = r#"{
+            "did": "did:plc:c6te24qg5hx54qgegqylpqkx",
+            "operation": {
+                "sig": "MDnVsVKDj-X2iHDtt9bX4xN8yIFruMexTHGFeLczgJZv-RNErz_Kg0mQDhEjezX158cP5-XBHPZ1nQ1K44OEFQ",
+                "prev": "bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku",
+                "type": "plc_operation",
+                "services": {
+                    "atproto_pds": {
+                        "type": "AtprotoPersonalDataServer",
+                        "endpoint": "https://magic.us-west.host.bsky.network"
+                    }
+                },
+                "alsoKnownAs": ["at://test.metaflame.dev", "at://alt.test.metaflame.dev"],
+                "rotationKeys": [
+                    "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg",
+                    "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK",
+                    "did:key:zQ3shb9nQ22CdsmTCKoeHnwTXXB9i12Uh2XT3vyCHhgaJWBUL"
+                ],
+                "verificationMethods": {
+                    "atproto": "did:key:zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe"
+                }
+            },
+            "cid": "bafyreihb7r2t3qegktlhxzqdr4gca77iov2w3putiiaut5qo33mj2ket2y",
+            "nullified": false,
+            "createdAt": "2025-01-19T21:21:08.428Z"
+        }"#;
+
+        let entry: AuditLogEntry = serde_json::from_str(entry_json).unwrap();
+        assert_eq!(entry.cid, entry.operation.get_cid_reference().unwrap());
+        assert_eq!(
+            entry.recovery_window_closes().to_rfc3339(),
+            "2025-01-22T21:21:08.428+00:00"
+        );
+    }
+}
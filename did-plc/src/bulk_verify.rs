@@ -0,0 +1,244 @@
+use std::collections::{HashMap, TryReserveError};
+
+use rayon::prelude::*;
+use serde_ipld_dagcbor::EncodeError;
+use sha2::Digest;
+use thiserror::Error;
+
+use crate::audit_log::AuditLogEntry;
+use crate::operation::VerificationError;
+use crate::{plc_operation_ref, DidPlc, PlcOperationRef};
+
+/// Result of [verify_export_entries].
+#[derive(Debug, Default)]
+pub struct BulkVerificationReport {
+    /// Number of entries which passed all checks
+    pub verified: usize,
+    /// Entries which failed a check, ordered by their index
+    pub failures: Vec<BulkVerificationFailure>,
+}
+
+impl BulkVerificationReport {
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct BulkVerificationFailure {
+    /// Index of the entry in the verified slice
+    pub index: usize,
+    pub did: DidPlc,
+    pub error: BulkVerificationError,
+}
+
+/// The first failed check of a single entry.
+#[derive(Error, Debug)]
+pub enum BulkVerificationError {
+    #[error("Failed to encode operation: {0}")]
+    Encode(#[from] EncodeError<TryReserveError>),
+    #[error(transparent)]
+    Cid(#[from] plc_operation_ref::Error),
+    #[error("Operation hashes to `{actual}`, but is listed as `{listed}`")]
+    CidMismatch {
+        listed: Box<PlcOperationRef>,
+        actual: Box<PlcOperationRef>,
+    },
+    #[error("Genesis operation hashes to `{actual}`")]
+    DidMismatch { actual: DidPlc },
+    #[error("Genesis operation follows other operations of the same DID")]
+    UnexpectedGenesis,
+    #[error("`prev` (`{0}`) is not an earlier operation of the same DID")]
+    BrokenPrev(Box<PlcOperationRef>),
+    #[error("`prev` is a tombstone")]
+    PrevIsTombstone,
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(#[from] VerificationError),
+}
+
+/// Verifies exported entries (e.g. from `/export`, see [crate::ExportReader]) in parallel.
+///
+/// `entries` may contain any number of DIDs, and must be in feed order. Each DID is checked
+/// independently (on a thread pool), and each of its operations must:
+/// - be listed under its actual CID
+/// - for genesis operations, hash to the listed DID, and be the first operation of the DID
+/// - otherwise, reference an earlier operation of the same DID in `prev`, which isn't a tombstone
+/// - be signed by a rotation key of the `prev` operation (or its own, for genesis)
+///
+/// Nullification is not checked, since the export reports it as of the time of export.
+///
+/// Operations of a single DID aren't checked in parallel: they need their `prev` operation
+/// (which may be forked from), and a DID rarely has more than a handful of them, so the DIDs
+/// spread the work evenly enough.
+pub fn verify_export_entries(entries: &[AuditLogEntry]) -> BulkVerificationReport {
+    let mut by_did: HashMap<&DidPlc, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        by_did.entry(&entry.did).or_default().push(index);
+    }
+
+    let mut failures: Vec<_> = by_did
+        .into_par_iter()
+        .flat_map_iter(|(_, indices)| verify_did_entries(entries, &indices))
+        .collect();
+    failures.sort_by_key(|failure| failure.index);
+
+    BulkVerificationReport {
+        verified: entries.len() - failures.len(),
+        failures,
+    }
+}
+
+/// Verifies the entries (at `indices`, in order) of a single DID
+fn verify_did_entries(
+    entries: &[AuditLogEntry],
+    indices: &[usize],
+) -> Vec<BulkVerificationFailure> {
+    // Index of each seen operation, by its actual CID
+    let mut seen: HashMap<PlcOperationRef, usize> = HashMap::with_capacity(indices.len());
+    let mut failures = Vec::new();
+
+    for &index in indices {
+        let entry = &entries[index];
+        match verify_entry(entries, entry, &seen) {
+            Ok(cid) => {
+                seen.insert(cid, index);
+            }
+            Err(error) => failures.push(BulkVerificationFailure {
+                index,
+                did: entry.did.clone(),
+                error,
+            }),
+        }
+    }
+
+    failures
+}
+
+/// Checks a single entry, returning its CID
+fn verify_entry(
+    entries: &[AuditLogEntry],
+    entry: &AuditLogEntry,
+    seen: &HashMap<PlcOperationRef, usize>,
+) -> Result<PlcOperationRef, BulkVerificationError> {
+    // The CID and the DID share the same hash, so the signed operation is only hashed once,
+    // and the encoding of the unsigned operation (for the signature) is shared with it
+    let (unsigned_bytes, signed_bytes) = entry.operation.to_dag_cbor()?;
+    let digest = sha2::Sha256::digest(&signed_bytes);
+    let cid = PlcOperationRef::from_sha256_digest(&digest)?;
+    if cid != entry.cid {
+        return Err(BulkVerificationError::CidMismatch {
+            listed: Box::new(entry.cid),
+            actual: Box::new(cid),
+        });
+    }
+
+    let rotation_keys = match entry.operation.prev() {
+        None => {
+            if !seen.is_empty() {
+                return Err(BulkVerificationError::UnexpectedGenesis);
+            }
+            let actual = DidPlc::from_sha256_digest(&digest);
+            if actual != entry.did {
+                return Err(BulkVerificationError::DidMismatch { actual });
+            }
+            entry.operation.rotation_keys()
+        }
+        Some(prev) => {
+            let prev_op = &seen
+                .get(&prev)
+                .map(|&prev_index| &entries[prev_index].operation)
+                .ok_or_else(|| BulkVerificationError::BrokenPrev(Box::new(prev)))?;
+            if prev_op.is_tombstone() {
+                return Err(BulkVerificationError::PrevIsTombstone);
+            }
+            prev_op.rotation_keys()
+        }
+    };
+
+    entry
+        .operation
+        .verify_serialized(&unsigned_bytes, rotation_keys)?;
+    Ok(cid)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+    use ecdsa::SigningKey;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::test_util::{did_key, random_key, signed_op};
+    use crate::AuditLog;
+
+    /// Export entries of `did_count` DIDs (genesis + update each), interleaved by DID
+    fn export_entries(did_count: usize) -> (Vec<AuditLogEntry>, Vec<SigningKey<Secp256k1>>) {
+        let created_at: DateTime<Utc> = Utc::now();
+        let keys: Vec<_> = (0..did_count).map(|_| random_key()).collect();
+
+        let logs: Vec<AuditLog> = keys
+            .iter()
+            .map(|key| {
+                let genesis = signed_op(&[did_key(key)], None, key);
+                let did = genesis.get_did_plc();
+                let genesis_ref = genesis.get_cid_reference().unwrap();
+
+                let mut log = AuditLog::default();
+                log.apply(&did, genesis, created_at).unwrap();
+                let update = signed_op(&[did_key(key)], Some(genesis_ref), key);
+                log.apply(&did, update, created_at + TimeDelta::seconds(1))
+                    .unwrap();
+                log
+            })
+            .collect();
+
+        let mut entries: Vec<_> = logs.iter().flat_map(|log| log.iter().cloned()).collect();
+        entries.sort_by_key(|entry| entry.created_at);
+        (entries, keys)
+    }
+
+    #[test]
+    fn valid_export() {
+        let (entries, _) = export_entries(16);
+
+        let report = verify_export_entries(&entries);
+        assert!(report.is_valid(), "{:?}", report.failures);
+        assert_eq!(report.verified, 32);
+    }
+
+    #[test]
+    fn report_failures() {
+        let (mut entries, keys) = export_entries(4);
+        let attacker = random_key();
+
+        // Genesis of DID 0 listed under DID 1
+        entries[0].did = entries[1].did.clone();
+        // Update of DID 2 replaced with one signed by an unknown key
+        let prev = entries[6].operation.prev();
+        entries[6].operation = signed_op(&[did_key(&keys[2])], prev, &attacker);
+        entries[6].cid = entries[6].operation.get_cid_reference().unwrap();
+        // Update of DID 3 listed under a wrong CID
+        entries[7].cid = entries[3].cid;
+
+        let report = verify_export_entries(&entries);
+        let failures: Vec<_> = report
+            .failures
+            .iter()
+            .map(|failure| (failure.index, &failure.error))
+            .collect();
+
+        assert_eq!(report.verified, 4);
+        assert_matches!(
+            failures.as_slice(),
+            [
+                (0, BulkVerificationError::DidMismatch { .. }),
+                // Since DID 0 lost its genesis, its update has no `prev`
+                (4, BulkVerificationError::BrokenPrev(_)),
+                (6, BulkVerificationError::InvalidSignature(_)),
+                (7, BulkVerificationError::CidMismatch { .. }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::io::BufReader;
+
+use chrono::SecondsFormat;
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use crate::audit_log::{AuditLog, AuditLogEntry};
+use crate::did_document::{DidDocument, DocumentData};
+use crate::export::{ExportCursor, ExportError, ExportReader};
+use crate::operation::{Signed, SignedOperation, SignedPlcOperation};
+use crate::DidPlc;
+
+pub const PLC_DIRECTORY_URL: &str = "https://plc.directory";
+
+/// A blocking client for the [PLC directory HTTP API](https://web.plc.directory/api/redoc).
+///
+/// Works with plc.directory, or any other server with the same API (see [PlcClient::new]).
+#[derive(Debug, Clone)]
+pub struct PlcClient {
+    base_url: Url,
+    http: Client,
+}
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("`{0}` cannot be used as a base URL")]
+    InvalidBaseUrl(Url),
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("PLC directory responded with {status}: {message}")]
+    Status { status: StatusCode, message: String },
+    #[error("Expected a `plc_operation`, got `{0}`")]
+    UnexpectedOperationType(String),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+}
+
+/// Error response body of the PLC directory
+#[derive(Deserialize)]
+struct ErrorMessage {
+    message: String,
+}
+
+impl PlcClient {
+    /// Creates a client for the directory at `base_url` (e.g. `http://localhost:2582`).
+    ///
+    /// The URL may have a path, all requests will be relative to it.
+    pub fn new(base_url: Url) -> Result<Self, ClientError> {
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidBaseUrl(base_url));
+        }
+        Ok(Self {
+            base_url,
+            http: Client::new(),
+        })
+    }
+
+    /// Creates a client for [plc.directory](https://plc.directory).
+    pub fn plc_directory() -> Self {
+        Self::new(Url::parse(PLC_DIRECTORY_URL).expect("Invalid PLC directory URL"))
+            .expect("Invalid PLC directory URL")
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// `GET /{did}`
+    pub fn get_document(&self, did: &DidPlc) -> Result<DidDocument, ClientError> {
+        self.get(did, &[])
+    }
+
+    /// `GET /{did}/log`, the active operations (without nullified ones)
+    pub fn get_log(&self, did: &DidPlc) -> Result<Vec<SignedOperation>, ClientError> {
+        self.get(did, &["log"])
+    }
+
+    /// `GET /{did}/log/audit`, all operations including nullified ones
+    pub fn get_audit_log(&self, did: &DidPlc) -> Result<AuditLog, ClientError> {
+        self.get(did, &["log", "audit"])
+    }
+
+    /// `GET /{did}/log/last`
+    pub fn get_last_operation(&self, did: &DidPlc) -> Result<SignedOperation, ClientError> {
+        self.get(did, &["log", "last"])
+    }
+
+    /// `GET /{did}/log/last`, if it is a `plc_operation`
+    /// (e.g. to use as the base of a new operation).
+    pub fn get_last_plc_operation(&self, did: &DidPlc) -> Result<SignedPlcOperation, ClientError> {
+        self.get_last_operation(did)?
+            .try_into()
+            .map_err(|op: SignedOperation| ClientError::UnexpectedOperationType(op.r#type().into()))
+    }
+
+    /// `GET /{did}/data`
+    pub fn get_data(&self, did: &DidPlc) -> Result<DocumentData, ClientError> {
+        self.get(did, &["data"])
+    }
+
+    /// `POST /{did}`, submits a signed operation
+    pub fn submit_operation<T: Serialize>(
+        &self,
+        did: &DidPlc,
+        signed_op: &Signed<T>,
+    ) -> Result<(), ClientError> {
+        let response = self.http.post(self.url(did, &[])).json(signed_op).send()?;
+        check_status(response)?;
+        Ok(())
+    }
+
+    /// `GET /export`, up to `count` entries (of all DIDs) after `cursor`
+    pub fn export(
+        &self,
+        cursor: &ExportCursor,
+        count: usize,
+    ) -> Result<Vec<AuditLogEntry>, ClientError> {
+        let entries = self
+            .export_reader(cursor, count)?
+            .collect::<Result<_, _>>()?;
+        Ok(entries)
+    }
+
+    /// Like [Self::export], but parses the entries while they are received.
+    pub fn export_reader(
+        &self,
+        cursor: &ExportCursor,
+        count: usize,
+    ) -> Result<ExportReader<BufReader<Response>>, ClientError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("Base URL was checked when creating the client")
+            .pop_if_empty()
+            .push("export");
+        url.query_pairs_mut()
+            .append_pair("count", &count.to_string());
+        if let Some(after) = cursor.after() {
+            url.query_pairs_mut()
+                .append_pair("after", &after.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+        }
+
+        let response = check_status(self.http.get(url).send()?)?;
+        Ok(ExportReader::new(BufReader::new(response)))
+    }
+
+    fn get<T: DeserializeOwned>(&self, did: &DidPlc, path: &[&str]) -> Result<T, ClientError> {
+        let response = self.http.get(self.url(did, path)).send()?;
+        Ok(check_status(response)?.json()?)
+    }
+
+    fn url(&self, did: &DidPlc, path: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("Base URL was checked when creating the client")
+            .pop_if_empty()
+            .push(&did.formatted_did())
+            .extend(path);
+        url
+    }
+}
+
+fn check_status(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().unwrap_or_default();
+    let message = match serde_json::from_str::<ErrorMessage>(&body) {
+        Ok(error) => error.message,
+        Err(_) => body,
+    };
+    Err(ClientError::Status { status, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+    use crate::test_util::PLC_OP_JSON;
+
+    const DID: &str = "did:plc:c6te24qg5hx54qgegqylpqkx";
+
+    /// The request line and body of a request received by [stand_in]
+    struct Received {
+        request_line: String,
+        body: String,
+    }
+
+    /// A stand-in directory which answers a single request with `status` and `body`
+    fn stand_in(status: u16, body: &'static str) -> (PlcClient, JoinHandle<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut request_body = vec![0; content_length];
+            reader.read_exact(&mut request_body).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 {status} Stand-in\r\nContent-Type: application/json\r\n\
+                Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+
+            Received {
+                request_line: request_line.trim().to_string(),
+                body: String::from_utf8(request_body).unwrap(),
+            }
+        });
+
+        (PlcClient::new(base_url).unwrap(), handle)
+    }
+
+    #[test]
+    fn get_last_plc_operation() {
+        let (client, server) = stand_in(200, PLC_OP_JSON);
+        let did = DidPlc::try_from(DID).unwrap();
+
+        let op = client.get_last_plc_operation(&did).unwrap();
+        assert_eq!(op.rotation_keys().len(), 3);
+        assert_eq!(
+            server.join().unwrap().request_line,
+            format!("GET /{DID}/log/last HTTP/1.1")
+        );
+    }
+
+    #[test]
+    fn base_url_with_path() {
+        let client = PlcClient::new(Url::parse("http://localhost:2582/plc/").unwrap()).unwrap();
+        let did = DidPlc::try_from(DID).unwrap();
+
+        assert_eq!(
+            client.url(&did, &["log", "audit"]).as_str(),
+            format!("http://localhost:2582/plc/{DID}/log/audit")
+        );
+    }
+
+    #[test]
+    fn submit_operation() {
+        let (client, server) = stand_in(200, "");
+        let did = DidPlc::try_from(DID).unwrap();
+        let op: SignedPlcOperation = serde_json::from_str(PLC_OP_JSON).unwrap();
+
+        client.submit_operation(&did, &op).unwrap();
+        let received = server.join().unwrap();
+        assert_eq!(received.request_line, format!("POST /{DID} HTTP/1.1"));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&received.body).unwrap(),
+            serde_json::from_str::<serde_json::Value>(PLC_OP_JSON).unwrap()
+        );
+    }
+
+    #[test]
+    fn error_status() {
+        let (client, server) = stand_in(404, r#"{"message":"DID not registered"}"#);
+        let did = DidPlc::try_from(DID).unwrap();
+
+        let err = client.get_data(&did).unwrap_err();
+        assert_matches!(
+            err,
+            ClientError::Status { status: StatusCode::NOT_FOUND, message } if message == "DID not registered"
+        );
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+use crate::did_plc::{self, DidPlc, DID_PLC_PREFIX};
+use crate::did_web::{self, DidWeb, DID_WEB_PREFIX};
+
+pub(crate) const DID_PREFIX: &str = "did:";
+
+/// A DID of one of the methods atproto supports, e.g. as an `alsoKnownAs` authority,
+/// or what a handle resolves to.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AtprotoDid {
+    Plc(DidPlc),
+    Web(DidWeb),
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    #[error("Invalid did:plc: {0}")]
+    InvalidDidPlc(#[from] did_plc::Error),
+    #[error(transparent)]
+    InvalidDidWeb(#[from] did_web::Error),
+    #[error("Unsupported DID method in `{0}` (must be did:plc or did:web)")]
+    UnsupportedMethod(String),
+}
+
+impl AtprotoDid {
+    pub fn formatted_did(&self) -> String {
+        match self {
+            AtprotoDid::Plc(did) => did.formatted_did(),
+            AtprotoDid::Web(did) => did.formatted_did(),
+        }
+    }
+}
+
+impl TryFrom<&str> for AtprotoDid {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.starts_with(DID_PLC_PREFIX) {
+            Ok(AtprotoDid::Plc(DidPlc::try_from(value)?))
+        } else if value.starts_with(DID_WEB_PREFIX) {
+            Ok(AtprotoDid::Web(DidWeb::try_from(value)?))
+        } else {
+            Err(Error::UnsupportedMethod(value.to_string()))
+        }
+    }
+}
+
+impl From<DidPlc> for AtprotoDid {
+    fn from(did: DidPlc) -> Self {
+        AtprotoDid::Plc(did)
+    }
+}
+
+impl From<DidWeb> for AtprotoDid {
+    fn from(did: DidWeb) -> Self {
+        AtprotoDid::Web(did)
+    }
+}
+
+impl Display for AtprotoDid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.formatted_did())
+    }
+}
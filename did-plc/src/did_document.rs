@@ -0,0 +1,251 @@
+use did_key::{DecodedPublicKey, DidKey};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::operation::{UnsignedOperation, UnsignedPlcOperation};
+use crate::{AkaUri, DidPlc, PlcService};
+
+const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+const MULTIKEY_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+const SECP256K1_CONTEXT: &str = "https://w3id.org/security/suites/secp256k1-2019/v1";
+const P256_CONTEXT: &str = "https://w3id.org/security/suites/ecdsa-2019/v1";
+
+const MULTIKEY_TYPE: &str = "Multikey";
+
+/// A W3C DID document, as served by [plc.directory](https://plc.directory) (`GET /{did}`).
+///
+/// Field order matters for `serde_json`, and matches the order used by plc.directory.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct DidDocument {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    pub id: String,
+    #[serde(rename = "alsoKnownAs")]
+    pub also_known_as: Vec<String>,
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: Vec<DocumentVerificationMethod>,
+    pub service: Vec<DocumentService>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct DocumentVerificationMethod {
+    /// Absolute ID (e.g. `did:plc:...#atproto`)
+    pub id: String,
+    pub r#type: String,
+    pub controller: String,
+    #[serde(rename = "publicKeyMultibase")]
+    pub public_key_multibase: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct DocumentService {
+    /// Relative ID (e.g. `#atproto_pds`)
+    pub id: String,
+    pub r#type: String,
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: String,
+}
+
+impl DidDocument {
+    /// Renders the document of `did`, given its latest operation.
+    ///
+    /// Follows the [reference implementation](https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/document.ts).
+    /// Verification methods and services keep the order of the operation's maps, like
+    /// plc.directory.
+    pub fn new(did: &DidPlc, op: &UnsignedPlcOperation) -> Self {
+        let did = did.formatted_did();
+        let mut context = vec![DID_CONTEXT.to_string(), MULTIKEY_CONTEXT.to_string()];
+
+        let verification_method = op
+            .verification_methods()
+            .iter()
+            .map(|(id, key)| {
+                let key_context = key_context(key).to_string();
+                if !context.contains(&key_context) {
+                    context.push(key_context);
+                }
+                DocumentVerificationMethod {
+                    id: format!("{did}#{id}"),
+                    r#type: MULTIKEY_TYPE.to_string(),
+                    controller: did.clone(),
+                    public_key_multibase: key.multibase_value().to_string(),
+                }
+            })
+            .collect();
+
+        let service = op
+            .services()
+            .iter()
+            .map(|(id, service)| DocumentService {
+                id: format!("#{id}"),
+                r#type: service.r#type.to_string(),
+                service_endpoint: service.endpoint.clone(),
+            })
+            .collect();
+
+        Self {
+            context,
+            also_known_as: op
+                .also_known_as()
+                .iter()
+                .map(|aka| aka.as_str().to_string())
+                .collect(),
+            id: did,
+            verification_method,
+            service,
+        }
+    }
+
+    /// Renders the document of `did`, given any latest operation.
+    ///
+    /// Returns `None` for tombstones, since a tombstoned DID has no document.
+    pub fn from_operation(did: &DidPlc, op: &UnsignedOperation) -> Option<Self> {
+        op.normalized().map(|op| Self::new(did, op))
+    }
+}
+
+/// The current state of a did:plc, as served by plc.directory (`GET /{did}/data`).
+///
+/// Field order matters for `serde_json`, and matches the order used by plc.directory.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentData {
+    pub did: DidPlc,
+    #[serde(rename = "verificationMethods")]
+    pub verification_methods: IndexMap<String, DidKey>,
+    #[serde(rename = "rotationKeys")]
+    pub rotation_keys: Vec<DidKey>,
+    #[serde(rename = "alsoKnownAs")]
+    pub also_known_as: Vec<AkaUri>,
+    pub services: IndexMap<String, PlcService>,
+}
+
+impl DocumentData {
+    /// The state of `did`, given its latest operation.
+    pub fn new(did: &DidPlc, op: &UnsignedPlcOperation) -> Self {
+        Self {
+            did: did.clone(),
+            verification_methods: op.verification_methods().clone(),
+            rotation_keys: op.rotation_keys().to_vec(),
+            also_known_as: op.also_known_as().to_vec(),
+            services: op.services().clone(),
+        }
+    }
+}
+
+fn key_context(key: &DidKey) -> &'static str {
+    match key.public_key() {
+        DecodedPublicKey::Secp256k1(_) => SECP256K1_CONTEXT,
+        DecodedPublicKey::NistP256(_) => P256_CONTEXT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::SignedPlcOperation;
+    use crate::test_util::PLC_OP_JSON;
+
+    const DID: &str = "did:plc:c6te24qg5hx54qgegqylpqkx";
+
+    // The document plc.directory renders for the operation above, if it is the latest one
+    const DOCUMENT_JSON: &str = r##"{
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+            "https://w3id.org/security/suites/secp256k1-2019/v1"
+        ],
+        "id": "did:plc:c6te24qg5hx54qgegqylpqkx",
+        "alsoKnownAs": [
+            "at://test.metaflame.dev",
+            "at://alt.test.metaflame.dev"
+        ],
+        "verificationMethod": [
+            {
+                "id": "did:plc:c6te24qg5hx54qgegqylpqkx#atproto",
+                "type": "Multikey",
+                "controller": "did:plc:c6te24qg5hx54qgegqylpqkx",
+                "publicKeyMultibase": "zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe"
+            }
+        ],
+        "service": [
+            {
+                "id": "#atproto_pds",
+                "type": "AtprotoPersonalDataServer",
+                "serviceEndpoint": "https://magic.us-west.host.bsky.network"
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn document_matches_directory() {
+        let op: SignedPlcOperation = serde_json::from_str(PLC_OP_JSON).unwrap();
+        let did = DidPlc::try_from(DID).unwrap();
+
+        let document = DidDocument::new(&did, &op);
+        let expected: DidDocument = serde_json::from_str(DOCUMENT_JSON).unwrap();
+        assert_eq!(document, expected);
+
+        // Also check the exact JSON shape
+        assert_eq!(
+            serde_json::to_value(&document).unwrap(),
+            serde_json::from_str::<serde_json::Value>(DOCUMENT_JSON).unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_operation_order() {
+        let key = DidKey::try_from(
+            "did:key:zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe".to_string(),
+        )
+        .unwrap();
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            vec![],
+            IndexMap::from([
+                ("atproto_label".to_string(), key.clone()),
+                ("atproto".to_string(), key),
+            ]),
+            vec![],
+            IndexMap::from([
+                (
+                    "atproto_pds".to_string(),
+                    PlcService::new_atproto_pds("https://pds.test").unwrap(),
+                ),
+                (
+                    "atproto_labeler".to_string(),
+                    PlcService::new_atproto_labeler("https://labeler.test").unwrap(),
+                ),
+            ]),
+        );
+        let document = DidDocument::new(&DidPlc::try_from(DID).unwrap(), &op);
+
+        let ids: Vec<_> = document
+            .verification_method
+            .iter()
+            .map(|method| method.id.rsplit('#').next().unwrap())
+            .chain(document.service.iter().map(|service| service.id.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "atproto_label",
+                "atproto",
+                "#atproto_pds",
+                "#atproto_labeler"
+            ]
+        );
+    }
+
+    #[test]
+    fn tombstone_has_no_document() {
+        let tombstone: UnsignedOperation = serde_json::from_str(
+            r#"{
+                "prev": "bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku",
+                "type": "plc_tombstone"
+            }"#,
+        )
+        .unwrap();
+        let did = DidPlc::try_from(DID).unwrap();
+
+        assert_eq!(DidDocument::from_operation(&did, &tombstone), None);
+    }
+}
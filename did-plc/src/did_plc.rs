@@ -4,28 +4,35 @@ use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use thiserror::Error;
 
-use crate::SignedPlcOperation;
+use crate::operation::Signed;
 
-const DID_PLC_PREFIX: &'static str = "did:plc:";
-const PLC_HASH_BASE32_LENGTH: usize = 24;
+pub(crate) const DID_PLC_PREFIX: &str = "did:plc:";
+pub(crate) const PLC_HASH_BASE32_LENGTH: usize = 24;
 
 // Rounded-up division isn't necessary for the current 24 characters - but just in case
 const PLC_HASH_BYTE_COUNT: usize = usize::div_ceil(PLC_HASH_BASE32_LENGTH * 5, 8);
 
 const PLC_HASH_ALPHABET: base32::Alphabet = base32::Alphabet::Rfc4648Lower { padding: false };
+/// Characters of [PLC_HASH_ALPHABET]
+pub(crate) const PLC_HASH_ALPHABET_CHARS: &str = "abcdefghijklmnopqrstuvwxyz234567";
 
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(try_from = "&str", into = "String")]
 pub struct DidPlc {
     hash_bytes: [u8; PLC_HASH_BYTE_COUNT],
 }
 
 impl DidPlc {
-    pub fn from_signed_op(signed_op: &SignedPlcOperation) -> Self {
+    pub fn from_signed_op<T: Serialize>(signed_op: &Signed<T>) -> Self {
         let signed_op_serialized = serde_ipld_dagcbor::ser::to_vec(signed_op)
             .expect("Signed operation serialization failed");
 
-        let signed_op_hash = sha2::Sha256::digest(&signed_op_serialized);
+        Self::from_sha256_digest(&sha2::Sha256::digest(&signed_op_serialized))
+    }
+
+    /// The did:plc of a signed genesis operation, given the SHA-256 of its dag-cbor encoding
+    /// (which is also the operation's CID hash).
+    pub(crate) fn from_sha256_digest(signed_op_hash: &[u8]) -> Self {
         debug_assert!(signed_op_hash.len() >= PLC_HASH_BYTE_COUNT); // Sanity check
         let signed_op_hash_trunc = &signed_op_hash[..PLC_HASH_BYTE_COUNT];
 
@@ -56,19 +63,19 @@ impl TryFrom<&str> for DidPlc {
     }
 }
 
-impl Into<String> for DidPlc {
-    fn into(self) -> String {
-        self.formatted_did()
+impl From<DidPlc> for String {
+    fn from(value: DidPlc) -> Self {
+        value.formatted_did()
     }
 }
 
-impl Into<String> for &DidPlc {
-    fn into(self) -> String {
-        self.formatted_did()
+impl From<&DidPlc> for String {
+    fn from(value: &DidPlc) -> Self {
+        value.formatted_did()
     }
 }
 
-#[derive(Error, Debug, Clone)]
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
 pub enum Error {
     #[error("Missing did:plc: prefix")]
     MissingPrefix,
@@ -120,7 +127,7 @@ impl Display for DidPlc {
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use super::*;
     #[test]
@@ -135,7 +142,7 @@ mod tests {
         // base32 = 5 bits per char
         // This means 15 bytes for a 24-char encoded hash
         let hash_bytes_trunc = &hash_value.as_slice()[..PLC_HASH_BYTE_COUNT];
-        let encoded_hash = base32::encode(PLC_HASH_ALPHABET, &hash_bytes_trunc);
+        let encoded_hash = base32::encode(PLC_HASH_ALPHABET, hash_bytes_trunc);
 
         let plc = format!("did:plc:{}", encoded_hash);
         let parsed_bytes = try_parse_formatted(&plc);
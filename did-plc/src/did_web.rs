@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+pub(crate) const DID_WEB_PREFIX: &str = "did:web:";
+// The port separator must be percent-encoded, since `:` separates path segments
+const ENCODED_COLON: &str = "%3a";
+
+const MAX_HOST_LENGTH: usize = 253;
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// A did:web identifier, i.e. a host (with an optional port) and an optional path.
+///
+/// See the [did:web spec](https://w3c-ccg.github.io/did-method-web/#method-specific-identifier).
+/// Note that atproto itself only supports hostname-level did:web identifiers (without paths).
+///
+/// Hosts are case-insensitive, and normalized to lowercase, so that equal DIDs compare equal.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DidWeb {
+    host: String,
+    port: Option<u16>,
+    // Percent-encoded segments
+    path: Vec<String>,
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    #[error("Missing did:web: prefix")]
+    MissingPrefix,
+    #[error("Invalid did:web host `{0}`")]
+    InvalidHost(String),
+    #[error("Invalid did:web port `{0}`")]
+    InvalidPort(String),
+    #[error("Invalid did:web path segment `{0}` (must be non-empty, a-z, 0-9, `.-_`, or percent-encoded)")]
+    InvalidPathSegment(String),
+}
+
+impl DidWeb {
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Path segments (still percent-encoded), empty for hostname-level DIDs
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    pub fn formatted_did(&self) -> String {
+        let mut did = format!("{DID_WEB_PREFIX}{}", self.host);
+        if let Some(port) = self.port {
+            did += &format!("%3A{port}");
+        }
+        for segment in &self.path {
+            did += &format!(":{segment}");
+        }
+        did
+    }
+
+    /// The HTTPS URL of the DID document
+    pub fn document_url(&self) -> String {
+        let mut url = format!("https://{}", self.host);
+        if let Some(port) = self.port {
+            url += &format!(":{port}");
+        }
+        if self.path.is_empty() {
+            url += "/.well-known";
+        }
+        for segment in &self.path {
+            url += &format!("/{segment}");
+        }
+        url + "/did.json"
+    }
+}
+
+impl TryFrom<&str> for DidWeb {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let identifier = value
+            .strip_prefix(DID_WEB_PREFIX)
+            .ok_or(Error::MissingPrefix)?;
+        let mut parts = identifier.split(':');
+        // `split` always yields at least one part
+        let host_and_port = parts.next().unwrap();
+
+        let (host, port) = match host_and_port.to_ascii_lowercase().find(ENCODED_COLON) {
+            Some(index) => {
+                let port = &host_and_port[index + ENCODED_COLON.len()..];
+                let port = port
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|port| *port != 0)
+                    .ok_or_else(|| Error::InvalidPort(port.to_string()))?;
+                (&host_and_port[..index], Some(port))
+            }
+            None => (host_and_port, None),
+        };
+        if !is_valid_host(host) {
+            return Err(Error::InvalidHost(host.to_string()));
+        }
+
+        let path = parts
+            .map(|segment| match is_valid_path_segment(segment) {
+                true => Ok(segment.to_string()),
+                false => Err(Error::InvalidPathSegment(segment.to_string())),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            path,
+        })
+    }
+}
+
+impl Display for DidWeb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.formatted_did())
+    }
+}
+
+/// A domain name (or IPv4 address): dot-separated labels of alphanumerics & inner hyphens
+fn is_valid_host(host: &str) -> bool {
+    host.len() <= MAX_HOST_LENGTH
+        && host.split('.').all(|label| {
+            (1..=MAX_LABEL_LENGTH).contains(&label.len())
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+/// Path segments may contain DID `idchar`s: alphanumerics, `.-_`, and percent-encoded bytes
+fn is_valid_path_segment(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let encoded = bytes.get(i + 1..i + 3);
+                if !encoded.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                    return false;
+                }
+                i += 3;
+            }
+            c if c.is_ascii_alphanumeric() || b".-_".contains(&c) => i += 1,
+            _ => return false,
+        }
+    }
+    !segment.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_only() {
+        let did = DidWeb::try_from("did:web:example.com").unwrap();
+        assert_eq!(did.host(), "example.com");
+        assert_eq!(did.port(), None);
+        assert!(did.path().is_empty());
+        assert_eq!(
+            did.document_url(),
+            "https://example.com/.well-known/did.json"
+        );
+    }
+
+    #[test]
+    fn port_and_path() {
+        let did = DidWeb::try_from("did:web:localhost%3A8443:user:alice%20b").unwrap();
+        assert_eq!(did.host(), "localhost");
+        assert_eq!(did.port(), Some(8443));
+        assert_eq!(did.path(), ["user", "alice%20b"]);
+        assert_eq!(
+            did.document_url(),
+            "https://localhost:8443/user/alice%20b/did.json"
+        );
+        assert_eq!(
+            did.formatted_did(),
+            "did:web:localhost%3A8443:user:alice%20b"
+        );
+    }
+
+    #[test]
+    fn host_is_lowercased() {
+        let did = DidWeb::try_from("did:web:Example.COM%3a8443:User").unwrap();
+        assert_eq!(did.host(), "example.com");
+        assert_eq!(did.path(), ["User"]);
+        assert_eq!(did.formatted_did(), "did:web:example.com%3A8443:User");
+        assert_eq!(
+            did,
+            DidWeb::try_from("did:web:example.com%3A8443:User").unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            DidWeb::try_from("did:plc:example"),
+            Err(Error::MissingPrefix)
+        );
+        assert_eq!(
+            DidWeb::try_from("did:web:exa_mple.com"),
+            Err(Error::InvalidHost("exa_mple.com".to_string()))
+        );
+        assert_eq!(
+            DidWeb::try_from("did:web:example.com%3A99999"),
+            Err(Error::InvalidPort("99999".to_string()))
+        );
+        assert_eq!(
+            DidWeb::try_from("did:web:example.com::user"),
+            Err(Error::InvalidPathSegment("".to_string()))
+        );
+        assert_eq!(
+            DidWeb::try_from("did:web:example.com:a%2"),
+            Err(Error::InvalidPathSegment("a%2".to_string()))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, BufRead, Lines};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::audit_log::AuditLogEntry;
+use crate::PlcOperationRef;
+
+/// Streaming parser for the JSON lines returned by `/export`.
+///
+/// Each line is a single [AuditLogEntry] (of any DID). Blank lines are skipped.
+/// Note that `nullified` reflects the state at the time of the export, operations
+/// nullified later are not exported again.
+pub struct ExportReader<R> {
+    lines: Lines<R>,
+    line_number: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to read export: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid export entry on line {line}: {source}")]
+    InvalidEntry {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+impl<R: BufRead> ExportReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ExportReader<R> {
+    type Item = Result<AuditLogEntry, ExportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                serde_json::from_str(&line).map_err(|source| ExportError::InvalidEntry {
+                    line: self.line_number,
+                    source,
+                }),
+            );
+        }
+    }
+}
+
+/// Position in the `/export` feed, which is paged by `createdAt` timestamps.
+///
+/// Entries are ordered by creation time, but several entries may share a timestamp (which only
+/// has millisecond precision), and a page may end between them. So the next page starts at the
+/// last seen timestamp again, and the entries seen at that timestamp are skipped by their CID.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ExportCursor {
+    // `createdAt` of the last seen entries
+    after: Option<DateTime<Utc>>,
+    // CIDs of the seen entries created at `after`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    seen: Vec<PlcOperationRef>,
+}
+
+impl ExportCursor {
+    /// A cursor at the start of the feed
+    pub fn start() -> Self {
+        Self::default()
+    }
+
+    /// A cursor after every entry created at (or before) `after`
+    pub fn new(after: DateTime<Utc>) -> Self {
+        Self {
+            after: Some(after),
+            seen: Vec::new(),
+        }
+    }
+
+    /// Creation time of the last seen entries
+    pub fn last_created_at(&self) -> Option<DateTime<Utc>> {
+        self.after
+    }
+
+    /// The `after` parameter of the next request (`None` at the start of the feed).
+    ///
+    /// plc.directory only returns entries created strictly after it, so this is a millisecond
+    /// before the last seen timestamp, if any entries were seen at that timestamp.
+    pub fn after(&self) -> Option<DateTime<Utc>> {
+        let after = self.after?;
+        match self.seen.is_empty() {
+            true => Some(after),
+            false => Some(after - TimeDelta::milliseconds(1)),
+        }
+    }
+
+    /// Moves the cursor past `entry`, returning whether it wasn't seen before (entries created
+    /// before the cursor position are assumed to be seen).
+    pub fn advance(&mut self, entry: &AuditLogEntry) -> bool {
+        match self.after {
+            Some(after) if entry.created_at < after => false,
+            Some(after) if entry.created_at == after => {
+                if self.seen.contains(&entry.cid) {
+                    return false;
+                }
+                self.seen.push(entry.cid);
+                true
+            }
+            _ => {
+                self.after = Some(entry.created_at);
+                self.seen = vec![entry.cid];
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    const EXPORT_JSONL: &str = r#"{"did":"did:plc:c6te24qg5hx54qgegqylpqkx","operation":{"sig":"MDnVsVKDj-X2iHDtt9bX4xN8yIFruMexTHGFeLczgJZv-RNErz_Kg0mQDhEjezX158cP5-XBHPZ1nQ1K44OEFQ","prev":"bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku","type":"plc_operation","services":{"atproto_pds":{"type":"AtprotoPersonalDataServer","endpoint":"https://magic.us-west.host.bsky.network"}},"alsoKnownAs":["at://test.metaflame.dev"],"rotationKeys":["did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg"],"verificationMethods":{"atproto":"did:key:zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe"}},"cid":"bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku","nullified":false,"createdAt":"2025-01-01T00:00:00.000Z"}
+
+{"did":"did:plc:c6te24qg5hx54qgegqylpqkx","operation":{"sig":"MDnVsVKDj-X2iHDtt9bX4xN8yIFruMexTHGFeLczgJZv-RNErz_Kg0mQDhEjezX158cP5-XBHPZ1nQ1K44OEFQ","prev":"bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku","type":"plc_tombstone"},"cid":"bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku","nullified":true,"createdAt":"2025-01-02T00:00:00.000Z"}
+"#;
+
+    #[test]
+    fn parse_export_lines() {
+        let entries: Vec<_> = ExportReader::new(EXPORT_JSONL.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation.r#type(), "plc_operation");
+        assert!(entries[1].operation.is_tombstone());
+        assert!(entries[1].nullified);
+    }
+
+    #[test]
+    fn invalid_line_number() {
+        let export = format!("{EXPORT_JSONL}{{\"did\":\"did:plc:invalid\"}}\n");
+        let mut reader = ExportReader::new(export.as_bytes());
+
+        assert_matches!(reader.next(), Some(Ok(_)));
+        assert_matches!(reader.next(), Some(Ok(_)));
+        assert_matches!(
+            reader.next(),
+            Some(Err(ExportError::InvalidEntry { line: 4, .. }))
+        );
+        assert_matches!(reader.next(), None);
+    }
+
+    #[test]
+    fn cursor_advances() {
+        let entries: Vec<_> = ExportReader::new(EXPORT_JSONL.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut cursor = ExportCursor::start();
+        assert!(entries.iter().all(|entry| cursor.advance(entry)));
+
+        let last = DateTime::parse_from_rfc3339("2025-01-02T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(cursor.after(), Some(last - TimeDelta::milliseconds(1)));
+        assert!(!cursor.advance(&entries[0]));
+        assert!(!cursor.advance(&entries[1]));
+    }
+
+    #[test]
+    fn shared_timestamp() {
+        let entry: AuditLogEntry = ExportReader::new(EXPORT_JSONL.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+        let other = AuditLogEntry {
+            cid: PlcOperationRef::from_signed_op(&entry.operation).unwrap(),
+            ..entry.clone()
+        };
+        assert_ne!(entry.cid, other.cid);
+
+        // A page ended after `entry`, the next one starts with it again
+        let mut cursor = ExportCursor::start();
+        assert!(cursor.advance(&entry));
+        assert!(!cursor.advance(&entry));
+        assert!(cursor.advance(&other));
+        assert!(cursor.after() < Some(entry.created_at));
+    }
+}
@@ -1,8 +1,17 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
 use thiserror::Error;
 
 pub const MAX_HANDLE_LENGTH: usize = 253;
 pub const MAX_SEGMENT_LENGTH: usize = 63;
 
+// Compiled once, since handles are validated for every parsed `alsoKnownAs` entry
+static RE_NUM_START: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^\d"#).unwrap());
+// Doesn't normalize! checks for a-z & A-Z instead
+static RE_SEGMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?$"#).unwrap());
+
 #[derive(Error, Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Error {
     #[error("Handle can be at most 253 characters long")]
@@ -45,15 +54,11 @@ pub fn validate_handle(input: &str) -> Result<(), Error> {
     }
 
     // Leading TLD digit disallowed
-    let re_num_start = regex::Regex::new(r#"^\d"#).unwrap();
     let last_segment = segments.last().unwrap();
-    if re_num_start.is_match(last_segment) {
+    if RE_NUM_START.is_match(last_segment) {
         return Err(Error::InvalidSegment(last_segment.to_string()));
     }
 
-    // Doesn't normalize! checks for a-z & A-Z instead
-    let re_segment = regex::Regex::new(r#"^[a-zA-Z0-9]([a-zA-Z0-9-]*[a-zA-Z0-9])?$"#).unwrap();
-
     // Segment validation
     for seg in segments {
         // Segments must be at most 63 characters long
@@ -61,7 +66,7 @@ pub fn validate_handle(input: &str) -> Result<(), Error> {
             return Err(Error::InvalidSegment(seg.to_string()));
         }
         // Must match pattern
-        if !re_segment.is_match(seg) {
+        if !RE_SEGMENT.is_match(seg) {
             return Err(Error::InvalidSegment(seg.to_string()));
         }
     }
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::did::{self, AtprotoDid};
+use crate::handle;
+
+/// A way of resolving handles, see the [handle resolution spec](https://atproto.com/specs/handle#handle-resolution).
+pub trait HandleResolver {
+    /// Short name of the method, for reporting which resolver gave which answer
+    fn name(&self) -> &str;
+
+    /// Resolves a valid, lowercase handle. `Ok(None)` if the handle isn't claimed via this method.
+    fn resolve(&self, handle: &str) -> Result<Option<AtprotoDid>, ResolveError>;
+}
+
+#[derive(Error, Debug)]
+pub enum ResolveError {
+    #[error("Resolved to an invalid DID: {0}")]
+    InvalidDid(#[from] did::Error),
+    #[error("Multiple DIDs are claimed: {}", .0.join(", "))]
+    MultipleDids(Vec<String>),
+    #[cfg(feature = "client")]
+    #[error("DNS lookup failed: {0}")]
+    Dns(#[from] hickory_resolver::error::ResolveError),
+    #[cfg(feature = "client")]
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[cfg(feature = "client")]
+    #[error("Server responded with {0}")]
+    Status(reqwest::StatusCode),
+}
+
+/// The answers of several resolvers for the same handle, see [resolve_handle].
+#[derive(Debug)]
+pub struct HandleResolution {
+    handle: String,
+    answers: Vec<(String, Result<Option<AtprotoDid>, ResolveError>)>,
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum HandleResolutionError {
+    #[error("Handle `{0}` doesn't resolve to a DID")]
+    NotFound(String),
+    #[error(
+        "Handle `{handle}` resolves to different DIDs: {}",
+        .answers.iter().map(|(name, did)| format!("{did} ({name})")).collect::<Vec<_>>().join(", ")
+    )]
+    Conflict {
+        handle: String,
+        answers: Vec<(String, AtprotoDid)>,
+    },
+}
+
+/// Resolves `handle` with each of the `resolvers` (in order), keeping all answers.
+///
+/// The handle is validated & normalized to lowercase first.
+pub fn resolve_handle(
+    handle: &str,
+    resolvers: &[&dyn HandleResolver],
+) -> Result<HandleResolution, handle::Error> {
+    handle::validate_handle(handle)?;
+    let handle = handle.to_ascii_lowercase();
+    let answers = resolvers
+        .iter()
+        .map(|resolver| (resolver.name().to_string(), resolver.resolve(&handle)))
+        .collect();
+    Ok(HandleResolution { handle, answers })
+}
+
+impl HandleResolution {
+    /// The normalized handle
+    pub fn handle(&self) -> &str {
+        &self.handle
+    }
+
+    /// The answer of each resolver, by name
+    pub fn answers(&self) -> &[(String, Result<Option<AtprotoDid>, ResolveError>)] {
+        &self.answers
+    }
+
+    /// The DID the resolvers agree on.
+    ///
+    /// Resolvers which failed or found nothing are ignored, but a handle claimed by different
+    /// DIDs (e.g. a stale DNS record & a new well-known file) is a conflict, not a match.
+    pub fn did(&self) -> Result<&AtprotoDid, HandleResolutionError> {
+        let found: Vec<_> = self
+            .answers
+            .iter()
+            .filter_map(|(name, answer)| match answer {
+                Ok(Some(did)) => Some((name, did)),
+                _ => None,
+            })
+            .collect();
+
+        let Some((_, did)) = found.first() else {
+            return Err(HandleResolutionError::NotFound(self.handle.clone()));
+        };
+        if found.iter().any(|(_, other)| other != did) {
+            return Err(HandleResolutionError::Conflict {
+                handle: self.handle.clone(),
+                answers: found
+                    .into_iter()
+                    .map(|(name, did)| (name.clone(), did.clone()))
+                    .collect(),
+            });
+        }
+        Ok(did)
+    }
+}
+
+/// A fixed set of handles, e.g. for tests or known-good answers.
+#[derive(Debug, Clone)]
+pub struct MemoryHandleResolver {
+    name: String,
+    handles: HashMap<String, AtprotoDid>,
+}
+
+impl MemoryHandleResolver {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            handles: HashMap::new(),
+        }
+    }
+
+    /// Adds (or replaces) a handle, ignoring case.
+    pub fn with_handle(mut self, handle: &str, did: AtprotoDid) -> Self {
+        self.handles.insert(handle.to_ascii_lowercase(), did);
+        self
+    }
+}
+
+impl HandleResolver for MemoryHandleResolver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn resolve(&self, handle: &str) -> Result<Option<AtprotoDid>, ResolveError> {
+        Ok(self.handles.get(handle).cloned())
+    }
+}
+
+/// The single DID claimed by `did=` entries (of TXT records), other entries are ignored
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+fn parse_txt_records(
+    records: impl IntoIterator<Item = String>,
+) -> Result<Option<AtprotoDid>, ResolveError> {
+    let mut dids: Vec<_> = records
+        .into_iter()
+        .filter_map(|record| record.strip_prefix("did=").map(str::to_string))
+        .collect();
+    match dids.len() {
+        0 => Ok(None),
+        1 => Ok(Some(AtprotoDid::try_from(dids.remove(0).as_str())?)),
+        _ => Err(ResolveError::MultipleDids(dids)),
+    }
+}
+
+#[cfg(feature = "client")]
+pub use network::*;
+
+#[cfg(feature = "client")]
+mod network {
+    use hickory_resolver::error::ResolveErrorKind;
+    use hickory_resolver::Resolver;
+    use reqwest::blocking::Client;
+    use reqwest::StatusCode;
+    use serde::Deserialize;
+    use url::Url;
+
+    use super::*;
+
+    /// Resolves handles via the `did=` TXT record at `_atproto.{handle}`.
+    pub struct DnsHandleResolver {
+        resolver: Resolver,
+    }
+
+    impl DnsHandleResolver {
+        /// Uses the system's DNS configuration (e.g. `/etc/resolv.conf`).
+        pub fn from_system_conf() -> std::io::Result<Self> {
+            Ok(Self {
+                resolver: Resolver::from_system_conf()?,
+            })
+        }
+
+        pub fn new(resolver: Resolver) -> Self {
+            Self { resolver }
+        }
+    }
+
+    impl HandleResolver for DnsHandleResolver {
+        fn name(&self) -> &str {
+            "DNS"
+        }
+
+        fn resolve(&self, handle: &str) -> Result<Option<AtprotoDid>, ResolveError> {
+            let lookup = match self.resolver.txt_lookup(format!("_atproto.{handle}.")) {
+                Ok(lookup) => lookup,
+                Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                    return Ok(None)
+                }
+                Err(err) => return Err(err.into()),
+            };
+            // A TXT record may be split into several character strings
+            parse_txt_records(lookup.iter().map(|txt| {
+                txt.txt_data()
+                    .iter()
+                    .map(|data| String::from_utf8_lossy(data))
+                    .collect()
+            }))
+        }
+    }
+
+    /// Resolves handles via `https://{handle}/.well-known/atproto-did`.
+    #[derive(Debug, Clone, Default)]
+    pub struct WellKnownHandleResolver {
+        http: Client,
+    }
+
+    impl WellKnownHandleResolver {
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    impl HandleResolver for WellKnownHandleResolver {
+        fn name(&self) -> &str {
+            "HTTPS well-known"
+        }
+
+        fn resolve(&self, handle: &str) -> Result<Option<AtprotoDid>, ResolveError> {
+            let response = self
+                .http
+                .get(format!("https://{handle}/.well-known/atproto-did"))
+                .send()?;
+            match response.status() {
+                StatusCode::NOT_FOUND => Ok(None),
+                status if status.is_success() => {
+                    Ok(Some(AtprotoDid::try_from(response.text()?.trim())?))
+                }
+                status => Err(ResolveError::Status(status)),
+            }
+        }
+    }
+
+    /// Resolves handles by asking a server (e.g. a PDS or AppView) via
+    /// `com.atproto.identity.resolveHandle`.
+    ///
+    /// The server does the actual resolution, so the answer is only as trustworthy as it is.
+    #[derive(Debug, Clone)]
+    pub struct XrpcHandleResolver {
+        service_url: Url,
+        name: String,
+        http: Client,
+    }
+
+    #[derive(Deserialize)]
+    struct ResolveHandleOutput {
+        did: String,
+    }
+
+    impl XrpcHandleResolver {
+        /// `service_url` is the base URL of the server, e.g. `https://bsky.social`.
+        pub fn new(service_url: Url) -> Self {
+            Self {
+                name: format!("XRPC ({service_url})"),
+                service_url,
+                http: Client::new(),
+            }
+        }
+
+        fn url(&self, handle: &str) -> Url {
+            let mut url = self
+                .service_url
+                .join("/xrpc/com.atproto.identity.resolveHandle")
+                .expect("XRPC path is a valid relative URL");
+            url.query_pairs_mut().append_pair("handle", handle);
+            url
+        }
+    }
+
+    impl HandleResolver for XrpcHandleResolver {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn resolve(&self, handle: &str) -> Result<Option<AtprotoDid>, ResolveError> {
+            let response = self.http.get(self.url(handle)).send()?;
+            match response.status() {
+                // Unresolvable handles are an `InvalidRequest`
+                StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => Ok(None),
+                status if status.is_success() => {
+                    let output: ResolveHandleOutput = response.json()?;
+                    Ok(Some(AtprotoDid::try_from(output.did.as_str())?))
+                }
+                status => Err(ResolveError::Status(status)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    const DID_PLC: &str = "did:plc:c6te24qg5hx54qgegqylpqkx";
+    const OTHER_DID_PLC: &str = "did:plc:ewvi7nxzyoun6zhxrhs64oiz";
+
+    fn did(value: &str) -> AtprotoDid {
+        AtprotoDid::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn agreeing_resolvers() {
+        let dns = MemoryHandleResolver::new("dns").with_handle("alice.test", did(DID_PLC));
+        let https = MemoryHandleResolver::new("https").with_handle("Alice.Test", did(DID_PLC));
+        let empty = MemoryHandleResolver::new("empty");
+
+        let resolution = resolve_handle("ALICE.test", &[&dns, &empty, &https]).unwrap();
+        assert_eq!(resolution.handle(), "alice.test");
+        assert_eq!(resolution.did(), Ok(&did(DID_PLC)));
+        assert_matches!(resolution.answers()[1], (_, Ok(None)));
+
+        assert_eq!(
+            resolve_handle("bob.test", &[&dns, &https]).unwrap().did(),
+            Err(HandleResolutionError::NotFound("bob.test".to_string()))
+        );
+        assert_eq!(
+            resolve_handle("bob", &[&dns]).unwrap_err(),
+            handle::Error::OnlyOneSegment
+        );
+    }
+
+    #[test]
+    fn conflicting_resolvers() {
+        let dns = MemoryHandleResolver::new("dns").with_handle("alice.test", did(DID_PLC));
+        let https =
+            MemoryHandleResolver::new("https").with_handle("alice.test", did("did:web:alice.test"));
+
+        let resolution = resolve_handle("alice.test", &[&dns, &https]).unwrap();
+        let err = resolution.did().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Handle `alice.test` resolves to different DIDs: {DID_PLC} (dns), did:web:alice.test (https)")
+        );
+    }
+
+    #[test]
+    fn txt_records() {
+        fn records(records: &[&str]) -> Vec<String> {
+            records.iter().map(|record| record.to_string()).collect()
+        }
+
+        assert_eq!(
+            parse_txt_records(records(&["v=spf1 -all", &format!("did={DID_PLC}")])).unwrap(),
+            Some(did(DID_PLC))
+        );
+        assert_eq!(parse_txt_records(records(&["v=spf1 -all"])).unwrap(), None);
+        assert_matches!(
+            parse_txt_records(records(&[
+                &format!("did={DID_PLC}"),
+                &format!("did={OTHER_DID_PLC}")
+            ])),
+            Err(ResolveError::MultipleDids(dids)) if dids.len() == 2
+        );
+        assert_matches!(
+            parse_txt_records(records(&["did=did:key:z123"])),
+            Err(ResolveError::InvalidDid(did::Error::UnsupportedMethod(_)))
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn xrpc_resolver() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        use url::Url;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_url =
+            Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request_line = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut request_line)
+                .unwrap();
+            let body = format!(r#"{{"did":"{DID_PLC}"}}"#);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            request_line
+        });
+
+        let resolver = XrpcHandleResolver::new(service_url);
+        assert_eq!(resolver.resolve("alice.test").unwrap(), Some(did(DID_PLC)));
+        assert_eq!(
+            server.join().unwrap().trim(),
+            "GET /xrpc/com.atproto.identity.resolveHandle?handle=alice.test HTTP/1.1"
+        );
+    }
+}
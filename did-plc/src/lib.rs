@@ -1,14 +1,14 @@
 #![feature(never_type)]
-#![feature(assert_matches)]
 use std::fs;
 use std::ops::Add;
 use std::path::Path;
+use std::sync::Arc;
 
-use derive_more::{Deref, DerefMut, From, Into};
+use derive_more::{Deref, From, Into};
 use ecdsa::hazmat::SignPrimitive;
 use ecdsa::signature::digest::generic_array::ArrayLength;
 use ecdsa::signature::rand_core::CryptoRngCore;
-use ecdsa::signature::Signer;
+use ecdsa::signature::{RandomizedSigner, Signer};
 use ecdsa::{Signature, SigningKey};
 use elliptic_curve::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
 use elliptic_curve::{CurveArithmetic, PrimeCurve, PublicKey};
@@ -16,19 +16,60 @@ use k256::Secp256k1;
 use p256::NistP256;
 
 mod aka_uri;
+mod audit_log;
+mod bulk_verify;
+#[cfg(feature = "client")]
+mod client;
+mod did;
+mod did_document;
 mod did_plc;
+mod did_web;
+mod export;
 mod handle;
+mod handle_resolver;
+mod mirror;
 mod operation;
+mod operation_log;
 mod plc_operation_ref;
 mod plc_service;
-
-pub use aka_uri::AkaUri;
+#[cfg(test)]
+mod test_util;
+mod vanity;
+
+pub use aka_uri::{AkaAuthority, AkaUri};
+pub use audit_log::{AuditLog, AuditLogEntry, Resolution, ResolutionError, RECOVERY_WINDOW};
+pub use bulk_verify::{
+    verify_export_entries, BulkVerificationError, BulkVerificationFailure, BulkVerificationReport,
+};
+#[cfg(feature = "client")]
+pub use client::{ClientError, PlcClient, PLC_DIRECTORY_URL};
+pub use did::AtprotoDid;
+pub use did_document::{DidDocument, DocumentData, DocumentService, DocumentVerificationMethod};
 use did_key::DidKey;
 pub use did_plc::DidPlc;
+pub use did_web::DidWeb;
+pub use export::{ExportCursor, ExportError, ExportReader};
 pub use handle::validate_handle;
-pub use operation::{SignatureBase64Url, SignedPlcOperation, UnsignedPlcOperation};
+pub use handle_resolver::{
+    resolve_handle, HandleResolution, HandleResolutionError, HandleResolver, MemoryHandleResolver,
+    ResolveError,
+};
+#[cfg(feature = "client")]
+pub use handle_resolver::{DnsHandleResolver, WellKnownHandleResolver, XrpcHandleResolver};
+pub use mirror::{ExportMirror, MirrorError};
+pub use operation::{
+    BuildError, ConstraintViolation, HandleStatus, HandleVerification, InvalidOperation,
+    LegacyCreateError, LintContext, OperationChange, OperationLint, ParseError, ParseMode,
+    PlcOperationBuilder, RecoveryError, SignatureBase64Url, SignatureError, Signed,
+    SignedLegacyCreate, SignedOperation, SignedPlcOperation, SignedPlcTombstone,
+    UnsignedLegacyCreate, UnsignedOperation, UnsignedPlcOperation, UnsignedPlcTombstone,
+    VerificationError, ATPROTO_LABELER_SERVICE_ID, ATPROTO_LABEL_VERIFICATION_METHOD_ID,
+    ATPROTO_PDS_SERVICE_ID, ATPROTO_VERIFICATION_METHOD_ID,
+};
+pub use operation_log::{validate_operation_log, OperationLogError, OperationLogReport};
 pub use plc_operation_ref::PlcOperationRef;
-pub use plc_service::PlcService;
+pub use plc_service::{PlcService, ServiceKind};
+pub use vanity::{find_vanity_did, VanityError, VanityPrefix, VanityProgress};
 
 pub trait PlcBlessedKeyCurve {}
 
@@ -36,9 +77,17 @@ impl PlcBlessedKeyCurve for NistP256 {}
 impl PlcBlessedKeyCurve for Secp256k1 {}
 
 pub trait PlcBlessedSigningKey {
+    /// Signs `bytes`, returning a raw (r||s), low-S signature
     fn sign_to_bytes(&self, bytes: &[u8]) -> Vec<u8>;
-
-    fn sign_plc_op(&self, unsigned_op: UnsignedPlcOperation) -> SignedPlcOperation;
+    /// Like [Self::sign_to_bytes], but with a random nonce, so each call produces a different
+    /// (equally valid) signature.
+    fn sign_to_bytes_with_rng(&self, rng: &mut dyn CryptoRngCore, bytes: &[u8]) -> Vec<u8>;
+
+    fn sign_plc_op(
+        &self,
+        unsigned_op: UnsignedPlcOperation,
+    ) -> Result<SignedPlcOperation, InvalidOperation>;
+    fn sign_plc_tombstone(&self, unsigned_op: UnsignedPlcTombstone) -> SignedPlcTombstone;
     fn new_random(rng: &mut impl CryptoRngCore) -> Self
     where
         Self: Sized;
@@ -59,16 +108,31 @@ where
     <<C as elliptic_curve::Curve>::FieldBytesSize as Add>::Output: ArrayLength<u8>,
     <C as CurveArithmetic>::Scalar: SignPrimitive<C>,
     SigningKey<C>: Signer<Signature<C>>,
+    SigningKey<C>: RandomizedSigner<Signature<C>>,
     SigningKey<C>: EncodePrivateKey,
     SigningKey<C>: DecodePrivateKey,
     PublicKey<C>: Into<DidKey>,
 {
     fn sign_to_bytes(&self, bytes: &[u8]) -> Vec<u8> {
         let signature: Signature<_> = Signer::sign(self, bytes);
+        let signature = signature.normalize_s().unwrap_or(signature);
+        signature.to_bytes().as_ref().to_vec()
+    }
+
+    fn sign_to_bytes_with_rng(&self, mut rng: &mut dyn CryptoRngCore, bytes: &[u8]) -> Vec<u8> {
+        let signature: Signature<_> = RandomizedSigner::sign_with_rng(self, &mut rng, bytes);
+        let signature = signature.normalize_s().unwrap_or(signature);
         signature.to_bytes().as_ref().to_vec()
     }
 
-    fn sign_plc_op(&self, unsigned_op: UnsignedPlcOperation) -> SignedPlcOperation {
+    fn sign_plc_op(
+        &self,
+        unsigned_op: UnsignedPlcOperation,
+    ) -> Result<SignedPlcOperation, InvalidOperation> {
+        unsigned_op.sign(self)
+    }
+
+    fn sign_plc_tombstone(&self, unsigned_op: UnsignedPlcTombstone) -> SignedPlcTombstone {
         unsigned_op.sign(self)
     }
 
@@ -83,7 +147,7 @@ where
     fn write_to_file(&self, path: &Path) -> std::io::Result<()> {
         match self.write_pkcs8_pem_file(path, LineEnding::LF) {
             Ok(()) => Ok(()),
-            Err(err) => Err(std::io::Error::new(std::io::ErrorKind::Other, err)),
+            Err(err) => Err(std::io::Error::other(err)),
         }
     }
 
@@ -94,17 +158,19 @@ where
     }
 }
 
-#[derive(Deref, DerefMut)]
+/// A shared signing key of any blessed curve.
+///
+/// Cloning is cheap, and the key may be sent to other threads (e.g. for [find_vanity_did]).
+#[derive(Deref, Clone)]
 pub struct PlcBlessedSigningKeyBox {
     #[deref(forward)]
-    #[deref_mut(forward)]
-    inner: Box<dyn PlcBlessedSigningKey>,
+    inner: Arc<dyn PlcBlessedSigningKey + Send + Sync>,
 }
 
-impl<K: PlcBlessedSigningKey + 'static> From<K> for PlcBlessedSigningKeyBox {
+impl<K: PlcBlessedSigningKey + Send + Sync + 'static> From<K> for PlcBlessedSigningKeyBox {
     fn from(value: K) -> Self {
         Self {
-            inner: Box::new(value),
+            inner: Arc::new(value),
         }
     }
 }
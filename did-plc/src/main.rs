@@ -1,8 +1,8 @@
-use std::collections::HashMap;
 use std::iter;
 
 use did_plc::{AkaUri, DidPlc, PlcService, SignedPlcOperation, UnsignedPlcOperation};
 use ecdsa::SigningKey;
+use indexmap::IndexMap;
 use k256::Secp256k1;
 
 mod helpers;
@@ -26,14 +26,14 @@ fn main() {
             .map(|key| elliptic_curve::PublicKey::from(key.verifying_key()))
             .map(|pub_key| pub_key.into())
             .collect(),
-        HashMap::from([(
+        IndexMap::from([(
             "atproto".to_string(),
             elliptic_curve::PublicKey::from(signing_key.verifying_key()).into(),
         )]),
         vec![aka_uri],
-        HashMap::from([(
+        IndexMap::from([(
             "atproto_pds".to_string(),
-            PlcService::new_atproto_pds(endpoint.to_owned()),
+            PlcService::new_atproto_pds(endpoint).expect("Invalid PDS endpoint"),
         )]),
     )
     .unwrap();
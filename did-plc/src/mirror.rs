@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::audit_log::{AuditLog, AuditLogEntry};
+use crate::export::{ExportCursor, ExportError, ExportReader};
+use crate::DidPlc;
+
+const ENTRIES_FILE: &str = "entries.jsonl";
+const CURSOR_FILE: &str = "cursor.json";
+
+/// A local, append-only replica of the `/export` feed.
+///
+/// Entries are appended to `entries.jsonl` in a directory as they are read, and the feed
+/// position is saved to `cursor.json`, so that mirroring can resume where it stopped.
+/// Optionally, only the entries of some DIDs are kept.
+///
+/// Nothing but the cursor is kept in memory, so reads ([Self::dids], [Self::audit_log]) scan
+/// the stored entries. An interrupted append may store some entries twice (the cursor is saved
+/// after them), which reads skip.
+pub struct ExportMirror {
+    dir: PathBuf,
+    entries_file: BufWriter<File>,
+    cursor: ExportCursor,
+    filter: Option<HashSet<DidPlc>>,
+}
+
+#[derive(Error, Debug)]
+pub enum MirrorError {
+    #[error("Mirror I/O failed: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+    #[error("Invalid mirror cursor: {0}")]
+    InvalidCursor(serde_json::Error),
+    #[error("A full page of entries was created at {0}, retry with a larger page size")]
+    PageTooSmall(DateTime<Utc>),
+    #[cfg(feature = "client")]
+    #[error(transparent)]
+    Client(#[from] crate::client::ClientError),
+}
+
+impl ExportMirror {
+    /// Opens (or creates) a mirror of all DIDs in `dir`.
+    pub fn open(dir: &Path) -> Result<Self, MirrorError> {
+        Self::open_with_filter(dir, None)
+    }
+
+    /// Opens (or creates) a mirror in `dir`, which only stores entries of `dids`.
+    ///
+    /// Entries which were stored before are kept, even if their DID isn't in `dids`.
+    pub fn open_filtered(
+        dir: &Path,
+        dids: impl IntoIterator<Item = DidPlc>,
+    ) -> Result<Self, MirrorError> {
+        Self::open_with_filter(dir, Some(dids.into_iter().collect()))
+    }
+
+    fn open_with_filter(dir: &Path, filter: Option<HashSet<DidPlc>>) -> Result<Self, MirrorError> {
+        fs::create_dir_all(dir)?;
+
+        let cursor = match fs::read_to_string(dir.join(CURSOR_FILE)) {
+            Ok(json) => serde_json::from_str(&json).map_err(MirrorError::InvalidCursor)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => ExportCursor::start(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let entries_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(ENTRIES_FILE))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            entries_file: BufWriter::new(entries_file),
+            cursor,
+            filter,
+        })
+    }
+
+    /// Position of the mirror in the feed, i.e. where the next page should start.
+    pub fn cursor(&self) -> &ExportCursor {
+        &self.cursor
+    }
+
+    /// Appends entries (in feed order) and advances the cursor past them.
+    ///
+    /// Entries which are filtered out, or were already seen (e.g. on overlapping pages),
+    /// are skipped. Returns the number of stored entries.
+    pub fn append(
+        &mut self,
+        entries: impl IntoIterator<Item = AuditLogEntry>,
+    ) -> Result<usize, MirrorError> {
+        self.append_results(entries.into_iter().map(Ok))
+    }
+
+    /// Parses `/export` JSON lines (see [ExportReader]) and appends each entry as it is read.
+    ///
+    /// On an invalid line, the entries before it are kept.
+    pub fn append_export(&mut self, export: impl BufRead) -> Result<usize, MirrorError> {
+        self.append_results(ExportReader::new(export).map(|entry| entry.map_err(Into::into)))
+    }
+
+    fn append_results(
+        &mut self,
+        entries: impl IntoIterator<Item = Result<AuditLogEntry, MirrorError>>,
+    ) -> Result<usize, MirrorError> {
+        let mut stored = 0;
+        let result = entries.into_iter().try_for_each(|entry| {
+            let entry = entry?;
+            if !self.cursor.advance(&entry) || !self.should_store(&entry) {
+                return Ok(());
+            }
+            serde_json::to_writer(&mut self.entries_file, &entry).map_err(io::Error::from)?;
+            self.entries_file.write_all(b"\n")?;
+            stored += 1;
+            Ok(())
+        });
+
+        // Entries must be persisted before the cursor moves past them
+        self.entries_file.flush()?;
+        self.entries_file.get_ref().sync_data()?;
+        self.save_cursor()?;
+        result.map(|()| stored)
+    }
+
+    fn should_store(&self, entry: &AuditLogEntry) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|dids| dids.contains(&entry.did))
+    }
+
+    fn save_cursor(&self) -> Result<(), MirrorError> {
+        let path = self.dir.join(CURSOR_FILE);
+        let temp_path = path.with_extension("tmp");
+        fs::write(
+            &temp_path,
+            serde_json::to_vec(&self.cursor).map_err(io::Error::from)?,
+        )?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// Reads the stored entries, in feed order
+    fn stored_entries(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<AuditLogEntry, ExportError>>, MirrorError> {
+        let file = File::open(self.dir.join(ENTRIES_FILE))?;
+        Ok(ExportReader::new(BufReader::new(file)))
+    }
+
+    /// DIDs with at least one stored entry
+    pub fn dids(&self) -> Result<HashSet<DidPlc>, MirrorError> {
+        let mut dids = HashSet::new();
+        for entry in self.stored_entries()? {
+            dids.insert(entry?.did);
+        }
+        Ok(dids)
+    }
+
+    /// The audit log of `did`, from the stored entries (`None` if there are none).
+    ///
+    /// The export doesn't update entries which were nullified after being exported, so
+    /// nullification is replayed from the `prev` references instead of relying on
+    /// the stored flags. Signatures are not verified.
+    pub fn audit_log(&self, did: &DidPlc) -> Result<Option<AuditLog>, MirrorError> {
+        let mut audit_log: Vec<AuditLogEntry> = Vec::new();
+
+        for entry in self.stored_entries()? {
+            let entry = entry?;
+            if entry.did != *did || audit_log.iter().any(|stored| stored.cid == entry.cid) {
+                continue;
+            }
+
+            // An operation nullifies every active operation after the one it references
+            let prev_index = entry.operation.prev().and_then(|prev| {
+                audit_log
+                    .iter()
+                    .position(|stored| !stored.nullified && stored.cid == prev)
+            });
+            if let Some(prev_index) = prev_index {
+                for stored in &mut audit_log[prev_index + 1..] {
+                    stored.nullified = true;
+                }
+            }
+
+            audit_log.push(AuditLogEntry {
+                nullified: false,
+                ..entry
+            });
+        }
+
+        Ok((!audit_log.is_empty()).then(|| AuditLog::new(audit_log)))
+    }
+
+    /// Fetches pages of `/export` (of up to `page_size` entries) until the mirror
+    /// catches up, returning the number of stored entries.
+    ///
+    /// Entries are stored while each page is received.
+    #[cfg(feature = "client")]
+    pub fn sync(
+        &mut self,
+        client: &crate::client::PlcClient,
+        page_size: usize,
+    ) -> Result<usize, MirrorError> {
+        let mut stored = 0;
+        loop {
+            let previous_cursor = self.cursor.clone();
+            let mut page_len = 0;
+            let page = client.export_reader(&self.cursor, page_size)?;
+            stored += self.append_results(
+                page.inspect(|_| page_len += 1)
+                    .map(|entry| entry.map_err(Into::into)),
+            )?;
+
+            if page_len < page_size {
+                return Ok(stored);
+            }
+            // The next page would be the same one
+            if let Some(after) = self
+                .cursor
+                .last_created_at()
+                .filter(|_| self.cursor == previous_cursor)
+            {
+                return Err(MirrorError::PageTooSmall(after));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::test_util::{created_at, did_key, random_keys, signed_op};
+
+    /// Audit log with keys `[a, b]`: genesis, an update signed by `b`,
+    /// and a recovery operation signed by `a` which nullifies the update
+    fn forked_log() -> (DidPlc, AuditLog) {
+        let keys: [_; 2] = random_keys();
+        let rotation_keys: Vec<_> = keys.iter().map(did_key).collect();
+        let start = created_at();
+
+        let genesis = signed_op(&rotation_keys, None, &keys[0]);
+        let did = genesis.get_did_plc();
+        let genesis_ref = genesis.get_cid_reference().unwrap();
+
+        let mut log = AuditLog::default();
+        log.apply(&did, genesis, start).unwrap();
+        let update = signed_op(&rotation_keys[1..], Some(genesis_ref), &keys[1]);
+        log.apply(&did, update, start + TimeDelta::seconds(1))
+            .unwrap();
+        let recovery = signed_op(&rotation_keys, Some(genesis_ref), &keys[0]);
+        log.apply(&did, recovery, start + TimeDelta::seconds(2))
+            .unwrap();
+
+        (did, log)
+    }
+
+    /// The entries as exported right after each operation, i.e. without later nullification
+    fn as_exported(log: &AuditLog) -> Vec<AuditLogEntry> {
+        log.iter()
+            .map(|entry| AuditLogEntry {
+                nullified: false,
+                ..entry.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replays_nullification() {
+        let dir = tempfile::tempdir().unwrap();
+        let (did, log) = forked_log();
+
+        let mut mirror = ExportMirror::open(dir.path()).unwrap();
+        assert_eq!(mirror.append(as_exported(&log)).unwrap(), 3);
+
+        let mirrored = mirror.audit_log(&did).unwrap().unwrap();
+        let nullified: Vec<_> = mirrored.iter().map(|entry| entry.nullified).collect();
+        assert_eq!(nullified, vec![false, true, false]);
+    }
+
+    #[test]
+    fn resume_from_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let (did, log) = forked_log();
+        let entries = as_exported(&log);
+
+        let mut mirror = ExportMirror::open(dir.path()).unwrap();
+        mirror.append(entries[..2].to_vec()).unwrap();
+        drop(mirror);
+
+        let mut mirror = ExportMirror::open(dir.path()).unwrap();
+        assert_eq!(
+            mirror.cursor().last_created_at(),
+            Some(entries[1].created_at)
+        );
+
+        // Overlapping pages don't produce duplicates
+        assert_eq!(mirror.append(entries[1..].to_vec()).unwrap(), 1);
+        assert_eq!(mirror.audit_log(&did).unwrap().unwrap().len(), 3);
+        assert_eq!(
+            mirror.cursor().last_created_at(),
+            Some(entries[2].created_at)
+        );
+    }
+
+    #[test]
+    fn filtered_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let (did, log) = forked_log();
+        let (other_did, other_log) = forked_log();
+
+        let mut mirror = ExportMirror::open_filtered(dir.path(), [did.clone()]).unwrap();
+        let mut entries: Vec<_> = as_exported(&other_log)
+            .into_iter()
+            .chain(as_exported(&log))
+            .collect();
+        entries.sort_by_key(|entry| entry.created_at);
+        let export: String = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        assert_eq!(mirror.append_export(export.as_bytes()).unwrap(), 3);
+
+        assert!(mirror.audit_log(&other_did).unwrap().is_none());
+        assert_eq!(mirror.dids().unwrap(), HashSet::from([did]));
+    }
+}
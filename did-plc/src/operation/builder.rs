@@ -0,0 +1,363 @@
+use did_key::DidKey;
+use indexmap::IndexMap;
+use thiserror::Error;
+
+use crate::aka_uri::{self, AkaUri};
+use crate::operation::constraints::InvalidOperation;
+use crate::operation::signed::SignedPlcOperation;
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::plc_operation_ref::{self, PlcOperationRef};
+use crate::plc_service::{self, PlcService};
+
+/// ID of the verification method used for atproto repo signing
+pub const ATPROTO_VERIFICATION_METHOD_ID: &str = "atproto";
+/// ID of the atproto PDS service
+pub const ATPROTO_PDS_SERVICE_ID: &str = "atproto_pds";
+/// ID of the verification method used for signing labels
+pub const ATPROTO_LABEL_VERIFICATION_METHOD_ID: &str = "atproto_label";
+/// ID of the atproto labeler service
+pub const ATPROTO_LABELER_SERVICE_ID: &str = "atproto_labeler";
+
+/// Builds the successor of an operation from a few edits, e.g.
+/// `PlcOperationBuilder::from_signed(&last_op)?.insert_rotation_key(0, key)?.build()?`,
+/// or modifies an unsigned operation (see [Self::edit]).
+#[derive(Debug, Clone)]
+pub struct PlcOperationBuilder {
+    rotation_keys: Vec<DidKey>,
+    verification_methods: IndexMap<String, DidKey>,
+    also_known_as: Vec<AkaUri>,
+    services: IndexMap<String, PlcService>,
+    prev: Option<PlcOperationRef>,
+    unknown_fields: IndexMap<String, serde_json::Value>,
+}
+
+#[derive(Error, Debug)]
+pub enum BuildError {
+    #[error("Failed to reference the previous operation: {0}")]
+    Prev(#[from] plc_operation_ref::Error),
+    #[error("Rotation key index {index} is out of range ({len} keys)")]
+    RotationKeyIndexOutOfRange { index: usize, len: usize },
+    #[error("Rotation key `{}` is already present", .0.formatted_value())]
+    DuplicateRotationKey(Box<DidKey>),
+    #[error("Rotation key `{}` is not present", .0.formatted_value())]
+    MissingRotationKey(Box<DidKey>),
+    #[error("Invalid handle: {0}")]
+    InvalidHandle(#[from] aka_uri::Error),
+    #[error("alsoKnownAs entry `{0}` is already present")]
+    DuplicateAlsoKnownAs(String),
+    #[error("alsoKnownAs entry `{0}` is not present")]
+    MissingAlsoKnownAs(String),
+    #[error("Invalid service: {0}")]
+    InvalidService(#[from] plc_service::Error),
+    #[error(transparent)]
+    InvalidOperation(#[from] InvalidOperation),
+}
+
+impl PlcOperationBuilder {
+    /// Starts with the state of `prev_op`, and references it as `prev`.
+    ///
+    /// Unknown fields of `prev_op` are kept, so [Self::build] fails until they are
+    /// dropped (see [Self::clear_unknown_fields]).
+    pub fn from_signed(prev_op: &SignedPlcOperation) -> Result<Self, BuildError> {
+        Ok(Self {
+            rotation_keys: prev_op.rotation_keys().to_vec(),
+            verification_methods: prev_op.verification_methods().clone(),
+            also_known_as: prev_op.also_known_as().to_vec(),
+            services: prev_op.services().clone(),
+            prev: Some(prev_op.get_cid_reference()?),
+            unknown_fields: prev_op.unknown_fields().clone(),
+        })
+    }
+
+    /// Starts with the state & `prev` of an unsigned operation, e.g. to remove a compromised key
+    /// from a recovery operation (see [UnsignedPlcOperation::new_recovery]) before signing it.
+    pub fn edit(op: &UnsignedPlcOperation) -> Self {
+        Self {
+            rotation_keys: op.rotation_keys().to_vec(),
+            verification_methods: op.verification_methods().clone(),
+            also_known_as: op.also_known_as().to_vec(),
+            services: op.services().clone(),
+            prev: op.prev(),
+            unknown_fields: op.unknown_fields().clone(),
+        }
+    }
+
+    /// Inserts a rotation key at `index` (0 is the highest priority), shifting the following keys.
+    pub fn insert_rotation_key(mut self, index: usize, key: DidKey) -> Result<Self, BuildError> {
+        if self.rotation_keys.contains(&key) {
+            return Err(BuildError::DuplicateRotationKey(Box::new(key)));
+        }
+        self.check_rotation_key_index(index, self.rotation_keys.len())?;
+        self.rotation_keys.insert(index, key);
+        Ok(self)
+    }
+
+    pub fn remove_rotation_key(mut self, key: &DidKey) -> Result<Self, BuildError> {
+        let index = self.rotation_key_index(key)?;
+        self.rotation_keys.remove(index);
+        Ok(self)
+    }
+
+    /// Moves a rotation key to `index`, shifting the keys in between.
+    pub fn move_rotation_key(mut self, key: &DidKey, index: usize) -> Result<Self, BuildError> {
+        let current_index = self.rotation_key_index(key)?;
+        self.check_rotation_key_index(index, self.rotation_keys.len() - 1)?;
+        let key = self.rotation_keys.remove(current_index);
+        self.rotation_keys.insert(index, key);
+        Ok(self)
+    }
+
+    /// Sets the endpoint of the `atproto_pds` service (adding it, if necessary).
+    pub fn set_pds_endpoint(mut self, endpoint: &str) -> Result<Self, BuildError> {
+        self.services.insert(
+            ATPROTO_PDS_SERVICE_ID.to_string(),
+            PlcService::new_atproto_pds(endpoint)?,
+        );
+        Ok(self)
+    }
+
+    /// Sets the `atproto` verification method (the repo signing key).
+    pub fn set_atproto_verification_method(mut self, key: DidKey) -> Self {
+        self.verification_methods
+            .insert(ATPROTO_VERIFICATION_METHOD_ID.to_string(), key);
+        self
+    }
+
+    /// Makes the identity a labeler: sets the `atproto_labeler` service (with a validated endpoint)
+    /// and the `atproto_label` verification method (the label signing key).
+    pub fn set_labeler(mut self, endpoint: &str, label_key: DidKey) -> Result<Self, BuildError> {
+        self.services.insert(
+            ATPROTO_LABELER_SERVICE_ID.to_string(),
+            PlcService::new_atproto_labeler(endpoint)?,
+        );
+        self.verification_methods
+            .insert(ATPROTO_LABEL_VERIFICATION_METHOD_ID.to_string(), label_key);
+        Ok(self)
+    }
+
+    /// Appends an `at://{handle}` alias (see [AkaUri::new_at]).
+    pub fn add_handle(mut self, handle: &str) -> Result<Self, BuildError> {
+        let aka_uri = AkaUri::new_at(handle)?;
+        if self
+            .also_known_as
+            .iter()
+            .any(|aka| aka.authority() == aka_uri.authority())
+        {
+            return Err(BuildError::DuplicateAlsoKnownAs(aka_uri.into()));
+        }
+        self.also_known_as.push(aka_uri);
+        Ok(self)
+    }
+
+    /// Removes the `at://{handle}` alias, ignoring case.
+    pub fn remove_handle(mut self, handle: &str) -> Result<Self, BuildError> {
+        let aka_uri = AkaUri::new_at(handle)?;
+        let Some(index) = self
+            .also_known_as
+            .iter()
+            .position(|aka| aka.authority() == aka_uri.authority())
+        else {
+            return Err(BuildError::MissingAlsoKnownAs(aka_uri.into()));
+        };
+        self.also_known_as.remove(index);
+        Ok(self)
+    }
+
+    /// Drops the fields which aren't part of the known `plc_operation` format,
+    /// which plc.directory would reject.
+    pub fn clear_unknown_fields(mut self) -> Self {
+        self.unknown_fields.clear();
+        self
+    }
+
+    /// The unsigned operation, if it satisfies the plc.directory constraints
+    /// and has valid service endpoints (see [UnsignedPlcOperation::validate_for_signing]).
+    pub fn build(self) -> Result<UnsignedPlcOperation, BuildError> {
+        let Ok(op) = UnsignedPlcOperation::new(
+            self.rotation_keys,
+            self.verification_methods,
+            self.also_known_as,
+            self.services,
+            self.prev,
+        );
+        let op = op.with_unknown_fields(self.unknown_fields);
+
+        let violations = op.validate_for_signing();
+        if !violations.is_empty() {
+            return Err(InvalidOperation(violations).into());
+        }
+        Ok(op)
+    }
+
+    fn rotation_key_index(&self, key: &DidKey) -> Result<usize, BuildError> {
+        self.rotation_keys
+            .iter()
+            .position(|rotation_key| rotation_key == key)
+            .ok_or_else(|| BuildError::MissingRotationKey(Box::new(key.clone())))
+    }
+
+    fn check_rotation_key_index(&self, index: usize, max: usize) -> Result<(), BuildError> {
+        if index > max {
+            return Err(BuildError::RotationKeyIndexOutOfRange {
+                index,
+                len: self.rotation_keys.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::test_util::{did_key, random_did_key, random_keys};
+    use crate::{ConstraintViolation, ParseMode};
+
+    /// A signed genesis operation with rotation keys `[a, b]`
+    fn genesis() -> (SignedPlcOperation, [DidKey; 2]) {
+        let keys: [_; 2] = random_keys();
+        let rotation_keys = keys.each_ref().map(did_key);
+        let op = UnsignedPlcOperation::new_genesis(
+            rotation_keys.to_vec(),
+            IndexMap::from([(
+                ATPROTO_VERIFICATION_METHOD_ID.to_string(),
+                rotation_keys[0].clone(),
+            )]),
+            vec![AkaUri::new_at("old.test").unwrap()],
+            IndexMap::new(),
+        )
+        .unwrap()
+        .sign(&keys[0])
+        .unwrap();
+        (op, rotation_keys)
+    }
+
+    #[test]
+    fn common_edits() {
+        let (genesis, [a, b]) = genesis();
+        let c = random_did_key();
+
+        let op = PlcOperationBuilder::from_signed(&genesis)
+            .unwrap()
+            .insert_rotation_key(0, c.clone())
+            .unwrap()
+            .move_rotation_key(&b, 1)
+            .unwrap()
+            .remove_rotation_key(&a)
+            .unwrap()
+            .set_pds_endpoint("https://pds.test")
+            .unwrap()
+            .set_atproto_verification_method(c.clone())
+            .remove_handle("old.test")
+            .unwrap()
+            .add_handle("new.test")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(op.prev(), Some(genesis.get_cid_reference().unwrap()));
+        assert_eq!(op.rotation_keys(), [c.clone(), b]);
+        assert_eq!(op.verification_methods()["atproto"], c);
+        assert_eq!(op.services()["atproto_pds"].endpoint, "https://pds.test");
+        assert_eq!(op.also_known_as(), [AkaUri::new_at("new.test").unwrap()]);
+    }
+
+    #[test]
+    fn invalid_edits() {
+        let (genesis, [a, _]) = genesis();
+        let builder = PlcOperationBuilder::from_signed(&genesis).unwrap();
+
+        assert_matches!(
+            builder.clone().insert_rotation_key(3, random_did_key()),
+            Err(BuildError::RotationKeyIndexOutOfRange { index: 3, len: 2 })
+        );
+        assert_matches!(
+            builder.clone().insert_rotation_key(0, a.clone()),
+            Err(BuildError::DuplicateRotationKey(_))
+        );
+        assert_matches!(
+            builder.clone().move_rotation_key(&a, 2),
+            Err(BuildError::RotationKeyIndexOutOfRange { index: 2, len: 2 })
+        );
+        assert_matches!(
+            builder.clone().remove_rotation_key(&random_did_key()),
+            Err(BuildError::MissingRotationKey(_))
+        );
+        assert_matches!(
+            builder.clone().add_handle("old.test"),
+            Err(BuildError::DuplicateAlsoKnownAs(_))
+        );
+        assert_matches!(
+            builder.remove_handle("other.test"),
+            Err(BuildError::MissingAlsoKnownAs(_))
+        );
+    }
+
+    #[test]
+    fn labeler() {
+        let (genesis, [a, _]) = genesis();
+        let builder = PlcOperationBuilder::from_signed(&genesis).unwrap();
+
+        let op = builder
+            .clone()
+            .set_labeler("https://labeler.test", a.clone())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(op.verification_methods()["atproto_label"], a);
+        assert_eq!(
+            op.services()["atproto_labeler"],
+            PlcService::new_atproto_labeler("https://labeler.test").unwrap()
+        );
+
+        assert_matches!(
+            builder.set_labeler("https://labeler.test/xrpc", a),
+            Err(BuildError::InvalidService(_))
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_fields() {
+        let (genesis, _) = genesis();
+        let mut json = serde_json::to_value(&genesis).unwrap();
+        json["futureField"] = 1.into();
+        let op = SignedPlcOperation::from_json(&json.to_string(), ParseMode::Lenient).unwrap();
+        let builder = PlcOperationBuilder::from_signed(&op).unwrap();
+
+        let Err(BuildError::InvalidOperation(InvalidOperation(violations))) =
+            builder.clone().build()
+        else {
+            panic!("Expected an invalid operation");
+        };
+        assert_eq!(
+            violations,
+            vec![ConstraintViolation::UnknownField("futureField".to_string())]
+        );
+        assert!(builder
+            .clear_unknown_fields()
+            .build()
+            .unwrap()
+            .unknown_fields()
+            .is_empty());
+    }
+
+    #[test]
+    fn validates_result() {
+        let (genesis, _) = genesis();
+        let mut builder = PlcOperationBuilder::from_signed(&genesis).unwrap();
+        for _ in 0..4 {
+            builder = builder.insert_rotation_key(0, random_did_key()).unwrap();
+        }
+
+        let Err(BuildError::InvalidOperation(InvalidOperation(violations))) = builder.build()
+        else {
+            panic!("Expected an invalid operation");
+        };
+        assert_eq!(
+            violations,
+            vec![ConstraintViolation::TooManyRotationKeys { count: 6 }]
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::operation::signed::{SignedOperation, SignedPlcOperation};
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::plc_service;
+
+// Limits enforced by plc.directory on incoming operations
+// https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
+
+/// Maximum size of a signed operation, in its dag-cbor encoding
+pub const MAX_OP_BYTES: usize = 4000;
+pub const MAX_AKA_ENTRIES: usize = 10;
+pub const MAX_AKA_LENGTH: usize = 256;
+/// Maximum rotation key count, as per the did:plc spec
+pub const MAX_ROTATION_ENTRIES: usize = 5;
+pub const MAX_SERVICE_ENTRIES: usize = 10;
+pub const MAX_SERVICE_TYPE_LENGTH: usize = 256;
+pub const MAX_SERVICE_ENDPOINT_LENGTH: usize = 512;
+pub const MAX_VERIFICATION_METHOD_ENTRIES: usize = 10;
+/// Maximum length of service & verification method IDs (map keys)
+pub const MAX_ID_LENGTH: usize = 32;
+
+/// The dag-cbor size of the `sig` entry: a 3-character key and an 86-character
+/// value (64 signature bytes, unpadded base64url), each with its header bytes.
+const SIG_ENTRY_BYTES: usize = (1 + 3) + (2 + 86);
+
+/// A single plc.directory constraint that an operation violates.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ConstraintViolation {
+    #[error("Operation is too large ({size} bytes, {MAX_OP_BYTES} maximum in dag-cbor encoding)")]
+    TooLarge { size: usize },
+    #[error("Too many alsoKnownAs entries ({count}, {MAX_AKA_ENTRIES} maximum)")]
+    TooManyAlsoKnownAs { count: usize },
+    #[error("alsoKnownAs entry `{0}` is too long ({MAX_AKA_LENGTH} characters maximum)")]
+    AlsoKnownAsTooLong(String),
+    #[error("Duplicate alsoKnownAs entry `{0}`")]
+    DuplicateAlsoKnownAs(String),
+    #[error("Too many rotation keys ({count}, {MAX_ROTATION_ENTRIES} maximum)")]
+    TooManyRotationKeys { count: usize },
+    #[error("Too many services ({count}, {MAX_SERVICE_ENTRIES} maximum)")]
+    TooManyServices { count: usize },
+    #[error("Service ID `{0}` is too long ({MAX_ID_LENGTH} characters maximum)")]
+    ServiceIdTooLong(String),
+    #[error("Type of service `{0}` is too long ({MAX_SERVICE_TYPE_LENGTH} characters maximum)")]
+    ServiceTypeTooLong(String),
+    #[error(
+        "Endpoint of service `{0}` is too long ({MAX_SERVICE_ENDPOINT_LENGTH} characters maximum)"
+    )]
+    ServiceEndpointTooLong(String),
+    /// Only reported by [UnsignedPlcOperation::validate_for_signing]
+    #[error("Endpoint of service `{0}` is invalid: {1}")]
+    InvalidServiceEndpoint(String, plc_service::Error),
+    #[error("Too many verification methods ({count}, {MAX_VERIFICATION_METHOD_ENTRIES} maximum)")]
+    TooManyVerificationMethods { count: usize },
+    #[error("Verification method ID `{0}` is too long ({MAX_ID_LENGTH} characters maximum)")]
+    VerificationMethodIdTooLong(String),
+    #[error("Unknown field `{0}` (plc.directory only accepts the known operation fields)")]
+    UnknownField(String),
+}
+
+/// An operation was refused, since it violates plc.directory constraints.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+#[error("Operation violates plc.directory constraints: {}", display_violations(.0))]
+pub struct InvalidOperation(pub Vec<ConstraintViolation>);
+
+fn display_violations(violations: &[ConstraintViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl UnsignedPlcOperation {
+    /// Checks the operation against the plc.directory constraints, returning all violations.
+    ///
+    /// The size limit applies to the signed operation, so the size of the `sig` entry
+    /// is accounted for (signatures always have the same encoded length).
+    pub fn validate(&self) -> Vec<ConstraintViolation> {
+        let mut violations = self.validate_fields();
+        if let Some(size) = dag_cbor_size(self) {
+            check_size(size + SIG_ENTRY_BYTES, &mut violations);
+        }
+        violations
+    }
+
+    /// Like [validate](Self::validate), but also requires every service endpoint to be
+    /// a valid https URL (see [PlcService::endpoint_url](crate::PlcService::endpoint_url)).
+    ///
+    /// plc.directory accepts any endpoint (e.g. `http://localhost:2583` for a local PDS),
+    /// so this stricter check only applies to operations signed here.
+    pub fn validate_for_signing(&self) -> Vec<ConstraintViolation> {
+        let mut violations = self.validate();
+        for (id, service) in sorted(self.services()) {
+            if let Err(err) = service.endpoint_url() {
+                violations.push(ConstraintViolation::InvalidServiceEndpoint(id.clone(), err));
+            }
+        }
+        violations
+    }
+
+    fn validate_fields(&self) -> Vec<ConstraintViolation> {
+        let mut violations = Vec::new();
+
+        let also_known_as = self.also_known_as();
+        if also_known_as.len() > MAX_AKA_ENTRIES {
+            violations.push(ConstraintViolation::TooManyAlsoKnownAs {
+                count: also_known_as.len(),
+            });
+        }
+        let mut seen_aka = HashSet::new();
+        for aka in also_known_as {
+            let aka = aka.as_str();
+            if aka.len() > MAX_AKA_LENGTH {
+                violations.push(ConstraintViolation::AlsoKnownAsTooLong(aka.into()));
+            }
+            if !seen_aka.insert(aka) {
+                violations.push(ConstraintViolation::DuplicateAlsoKnownAs(aka.into()));
+            }
+        }
+
+        let rotation_key_count = self.rotation_keys().len();
+        if rotation_key_count > MAX_ROTATION_ENTRIES {
+            violations.push(ConstraintViolation::TooManyRotationKeys {
+                count: rotation_key_count,
+            });
+        }
+
+        let services = self.services();
+        if services.len() > MAX_SERVICE_ENTRIES {
+            violations.push(ConstraintViolation::TooManyServices {
+                count: services.len(),
+            });
+        }
+        for (id, service) in sorted(services) {
+            if id.len() > MAX_ID_LENGTH {
+                violations.push(ConstraintViolation::ServiceIdTooLong(id.clone()));
+            }
+            if service.r#type.as_str().len() > MAX_SERVICE_TYPE_LENGTH {
+                violations.push(ConstraintViolation::ServiceTypeTooLong(id.clone()));
+            }
+            if service.endpoint.len() > MAX_SERVICE_ENDPOINT_LENGTH {
+                violations.push(ConstraintViolation::ServiceEndpointTooLong(id.clone()));
+            }
+        }
+
+        let verification_methods = self.verification_methods();
+        if verification_methods.len() > MAX_VERIFICATION_METHOD_ENTRIES {
+            violations.push(ConstraintViolation::TooManyVerificationMethods {
+                count: verification_methods.len(),
+            });
+        }
+        for (id, _) in sorted(verification_methods) {
+            if id.len() > MAX_ID_LENGTH {
+                violations.push(ConstraintViolation::VerificationMethodIdTooLong(id.clone()));
+            }
+        }
+
+        for field in self.unknown_fields().keys() {
+            violations.push(ConstraintViolation::UnknownField(field.clone()));
+        }
+
+        violations
+    }
+}
+
+impl SignedPlcOperation {
+    /// Checks the operation against the plc.directory constraints, returning all violations.
+    pub fn validate(&self) -> Vec<ConstraintViolation> {
+        let mut violations = self.unsigned().validate_fields();
+        if let Some(size) = dag_cbor_size(self) {
+            check_size(size, &mut violations);
+        }
+        violations
+    }
+}
+
+impl SignedOperation {
+    /// Checks the operation against the plc.directory constraints, returning all violations.
+    ///
+    /// Only the size is limited for operations other than `plc_operation`.
+    pub fn validate(&self) -> Vec<ConstraintViolation> {
+        let mut violations = match self.as_plc_operation() {
+            Some(op) => op.validate_fields(),
+            None => Vec::new(),
+        };
+        if let Some(size) = dag_cbor_size(self) {
+            check_size(size, &mut violations);
+        }
+        violations
+    }
+}
+
+/// Map entries sorted by key, so that violations are reported in a stable order
+fn sorted<K: Ord, V>(map: &IndexMap<K, V>) -> Vec<(&K, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+/// Encoded size, or `None` if the operation can't be encoded at all
+/// (which signing and CID computation will report on their own)
+fn dag_cbor_size(op: &impl Serialize) -> Option<usize> {
+    serde_ipld_dagcbor::ser::to_vec(op)
+        .ok()
+        .map(|bytes| bytes.len())
+}
+
+fn check_size(size: usize, violations: &mut Vec<ConstraintViolation>) {
+    if size > MAX_OP_BYTES {
+        violations.push(ConstraintViolation::TooLarge { size });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use did_key::DidKey;
+    use ecdsa::SigningKey;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::test_util::{did_key, random_did_key, random_key};
+    use crate::{AkaUri, PlcService, ServiceKind};
+
+    fn unsigned_op(
+        rotation_keys: Vec<DidKey>,
+        also_known_as: Vec<AkaUri>,
+        services: IndexMap<String, PlcService>,
+    ) -> UnsignedPlcOperation {
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            rotation_keys,
+            IndexMap::new(),
+            also_known_as,
+            services,
+        );
+        op
+    }
+
+    #[test]
+    fn valid_op() {
+        let key = random_key();
+        let op = unsigned_op(
+            vec![did_key(&key)],
+            vec![AkaUri::new_at("alice.example.com").unwrap()],
+            IndexMap::from([(
+                "atproto_pds".to_string(),
+                PlcService::new_atproto_pds("https://pds.example.com").unwrap(),
+            )]),
+        );
+
+        assert_eq!(op.validate(), vec![]);
+        assert_eq!(op.sign(&key).unwrap().validate(), vec![]);
+    }
+
+    #[test]
+    fn sig_entry_size() {
+        let key = random_key();
+        let op = unsigned_op(vec![did_key(&key)], vec![], IndexMap::new());
+        let unsigned_size = dag_cbor_size(&op).unwrap();
+        let signed_size = dag_cbor_size(&op.sign(&key).unwrap()).unwrap();
+
+        assert_eq!(unsigned_size + SIG_ENTRY_BYTES, signed_size);
+    }
+
+    #[test]
+    fn reject_duplicate_aka() {
+        let aka = AkaUri::new_at("alice.example.com").unwrap();
+        let op = unsigned_op(vec![], vec![aka.clone(), aka], IndexMap::new());
+
+        assert_eq!(
+            op.validate(),
+            vec![ConstraintViolation::DuplicateAlsoKnownAs(
+                "at://alice.example.com".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn reject_too_many_rotation_keys() {
+        let keys: Vec<_> = (0..=MAX_ROTATION_ENTRIES)
+            .map(|_| random_did_key())
+            .collect();
+        let op = unsigned_op(keys, vec![], IndexMap::new());
+
+        assert_eq!(
+            op.validate(),
+            vec![ConstraintViolation::TooManyRotationKeys {
+                count: MAX_ROTATION_ENTRIES + 1
+            }]
+        );
+    }
+
+    #[test]
+    fn reject_long_service_fields() {
+        let id = "a".repeat(MAX_ID_LENGTH + 1);
+        let service = PlcService {
+            r#type: "t".repeat(MAX_SERVICE_TYPE_LENGTH + 1).into(),
+            endpoint: format!("https://{}", "e".repeat(MAX_SERVICE_ENDPOINT_LENGTH)),
+        };
+        let op = unsigned_op(vec![], vec![], IndexMap::from([(id.clone(), service)]));
+
+        assert_eq!(
+            op.validate(),
+            vec![
+                ConstraintViolation::ServiceIdTooLong(id.clone()),
+                ConstraintViolation::ServiceTypeTooLong(id.clone()),
+                ConstraintViolation::ServiceEndpointTooLong(id),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_endpoint() {
+        let service = PlcService {
+            r#type: ServiceKind::AtprotoPersonalDataServer,
+            endpoint: "http://pds.example.com".to_string(),
+        };
+        let op = unsigned_op(
+            vec![],
+            vec![],
+            IndexMap::from([("atproto_pds".to_string(), service)]),
+        );
+
+        // Accepted by plc.directory, but not signed here
+        assert_eq!(op.validate(), vec![]);
+        assert_matches!(
+            op.validate_for_signing().as_slice(),
+            [ConstraintViolation::InvalidServiceEndpoint(id, plc_service::Error::NotHttps { .. })]
+                if id == "atproto_pds"
+        );
+    }
+
+    /// An operation with atproto_pds services, padded to exactly `size` bytes once signed
+    fn op_with_signed_size(key: &SigningKey<Secp256k1>, size: usize) -> UnsignedPlcOperation {
+        // Endpoints of 256..=512 characters all have a 3-byte dag-cbor header
+        const BASE_LENGTH: usize = 384;
+        let service = |length: usize| {
+            PlcService::new_atproto_pds(&format!("https://{}", "e".repeat(length - 8))).unwrap()
+        };
+        let signed_size = |services: &IndexMap<_, _>| {
+            let op = unsigned_op(vec![did_key(key)], vec![], services.clone());
+            dag_cbor_size(&op.sign_unchecked(key)).unwrap()
+        };
+
+        let mut services = IndexMap::new();
+        loop {
+            let mut more = services.clone();
+            more.insert(format!("service_{}", services.len()), service(BASE_LENGTH));
+            if signed_size(&more) > size {
+                break;
+            }
+            services = more;
+        }
+        let mut padding = size - signed_size(&services);
+        for endpoint in services.values_mut() {
+            let extra = padding.min(MAX_SERVICE_ENDPOINT_LENGTH - BASE_LENGTH);
+            *endpoint = service(BASE_LENGTH + extra);
+            padding -= extra;
+        }
+
+        assert_eq!(signed_size(&services), size);
+        unsigned_op(vec![did_key(key)], vec![], services)
+    }
+
+    #[test]
+    fn size_limit_boundary() {
+        let key = random_key();
+
+        let op = op_with_signed_size(&key, MAX_OP_BYTES);
+        assert_eq!(op.validate(), vec![]);
+        assert_eq!(op.sign(&key).unwrap().validate(), vec![]);
+
+        let size = MAX_OP_BYTES + 1;
+        let op = op_with_signed_size(&key, size);
+        assert_eq!(op.validate(), vec![ConstraintViolation::TooLarge { size }]);
+        assert_eq!(
+            op.sign_unchecked(&key).validate(),
+            vec![ConstraintViolation::TooLarge { size }]
+        );
+    }
+
+    #[test]
+    fn sign_rejects_oversized_op() {
+        let key = random_key();
+        let services = (0..MAX_SERVICE_ENTRIES)
+            .map(|i| {
+                let endpoint = format!("https://{}", "e".repeat(MAX_SERVICE_ENDPOINT_LENGTH - 8));
+                (
+                    format!("service_{i}"),
+                    PlcService::new_atproto_pds(&endpoint).unwrap(),
+                )
+            })
+            .collect();
+        let also_known_as = (0..MAX_AKA_ENTRIES)
+            .map(|i| {
+                let label = "a".repeat(60);
+                AkaUri::new_at(&format!("{i}{label}.{label}.{label}.example.com")).unwrap()
+            })
+            .collect();
+        let op = unsigned_op(vec![did_key(&key)], also_known_as, services);
+
+        let err = op.clone().sign(&key).unwrap_err();
+        assert_matches!(err.0.as_slice(), [ConstraintViolation::TooLarge { .. }]);
+
+        // The unchecked path still signs it, and the size estimate matches the real size
+        assert_eq!(op.sign_unchecked(&key).validate(), err.0);
+    }
+}
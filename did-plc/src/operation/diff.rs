@@ -0,0 +1,343 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use did_key::DidKey;
+use indexmap::IndexMap;
+
+use crate::aka_uri::AkaUri;
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::plc_service::PlcService;
+
+/// A single change between two operations (see [UnsignedPlcOperation::diff]).
+///
+/// Indexes are positions in the respective arrays. For rotation keys, a lower index means
+/// a higher authority (see [crate::AuditLog]).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OperationChange {
+    RotationKeyAdded {
+        key: DidKey,
+        index: usize,
+    },
+    RotationKeyRemoved {
+        key: DidKey,
+        index: usize,
+    },
+    /// The key is in both operations, but at a different index
+    RotationKeyMoved {
+        key: DidKey,
+        from: usize,
+        to: usize,
+    },
+    VerificationMethodAdded {
+        id: String,
+        key: DidKey,
+    },
+    VerificationMethodRemoved {
+        id: String,
+        key: DidKey,
+    },
+    VerificationMethodChanged {
+        id: String,
+        from: DidKey,
+        to: DidKey,
+    },
+    ServiceAdded {
+        id: String,
+        service: PlcService,
+    },
+    ServiceRemoved {
+        id: String,
+        service: PlcService,
+    },
+    /// The service type or endpoint changed
+    ServiceChanged {
+        id: String,
+        from: PlcService,
+        to: PlcService,
+    },
+    AlsoKnownAsAdded {
+        uri: AkaUri,
+        index: usize,
+    },
+    AlsoKnownAsRemoved {
+        uri: AkaUri,
+        index: usize,
+    },
+    /// The alias is in both operations, but at a different index
+    AlsoKnownAsMoved {
+        uri: AkaUri,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl UnsignedPlcOperation {
+    /// Changes from this operation to `new` (e.g. a successor of this operation), ordered by field:
+    /// rotation keys, verification methods & services (by ID), and aliases.
+    ///
+    /// `prev` is not compared, since it differs between any operation and its successor.
+    pub fn diff(&self, new: &UnsignedPlcOperation) -> Vec<OperationChange> {
+        let mut changes = Vec::new();
+
+        for (key, change) in diff_arrays(self.rotation_keys(), new.rotation_keys()) {
+            changes.push(match change {
+                ArrayChange::Added(index) => OperationChange::RotationKeyAdded { key, index },
+                ArrayChange::Removed(index) => OperationChange::RotationKeyRemoved { key, index },
+                ArrayChange::Moved { from, to } => {
+                    OperationChange::RotationKeyMoved { key, from, to }
+                }
+            });
+        }
+
+        for (id, change) in diff_maps(self.verification_methods(), new.verification_methods()) {
+            changes.push(match change {
+                MapChange::Added(key) => OperationChange::VerificationMethodAdded { id, key },
+                MapChange::Removed(key) => OperationChange::VerificationMethodRemoved { id, key },
+                MapChange::Changed { from, to } => {
+                    OperationChange::VerificationMethodChanged { id, from, to }
+                }
+            });
+        }
+
+        for (id, change) in diff_maps(self.services(), new.services()) {
+            changes.push(match change {
+                MapChange::Added(service) => OperationChange::ServiceAdded { id, service },
+                MapChange::Removed(service) => OperationChange::ServiceRemoved { id, service },
+                MapChange::Changed { from, to } => OperationChange::ServiceChanged { id, from, to },
+            });
+        }
+
+        for (uri, change) in diff_arrays(self.also_known_as(), new.also_known_as()) {
+            changes.push(match change {
+                ArrayChange::Added(index) => OperationChange::AlsoKnownAsAdded { uri, index },
+                ArrayChange::Removed(index) => OperationChange::AlsoKnownAsRemoved { uri, index },
+                ArrayChange::Moved { from, to } => {
+                    OperationChange::AlsoKnownAsMoved { uri, from, to }
+                }
+            });
+        }
+
+        changes
+    }
+}
+
+enum ArrayChange {
+    Added(usize),
+    Removed(usize),
+    Moved { from: usize, to: usize },
+}
+
+/// Removed & moved items (in old order), followed by added items (in new order)
+fn diff_arrays<T: Clone + Eq>(old: &[T], new: &[T]) -> Vec<(T, ArrayChange)> {
+    let mut changes = Vec::new();
+    for (from, item) in old.iter().enumerate() {
+        match new.iter().position(|new_item| new_item == item) {
+            None => changes.push((item.clone(), ArrayChange::Removed(from))),
+            Some(to) if to != from => changes.push((item.clone(), ArrayChange::Moved { from, to })),
+            Some(_) => {}
+        }
+    }
+    for (index, item) in new.iter().enumerate() {
+        if !old.contains(item) {
+            changes.push((item.clone(), ArrayChange::Added(index)));
+        }
+    }
+    changes
+}
+
+enum MapChange<V> {
+    Added(V),
+    Removed(V),
+    Changed { from: V, to: V },
+}
+
+/// Changed entries, ordered by key
+fn diff_maps<V: Clone + Eq>(
+    old: &IndexMap<String, V>,
+    new: &IndexMap<String, V>,
+) -> Vec<(String, MapChange<V>)> {
+    let ids: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    ids.into_iter()
+        .filter_map(|id| {
+            let change = match (old.get(id), new.get(id)) {
+                (Some(from), Some(to)) if from == to => return None,
+                (Some(from), Some(to)) => MapChange::Changed {
+                    from: from.clone(),
+                    to: to.clone(),
+                },
+                (Some(value), None) => MapChange::Removed(value.clone()),
+                (None, Some(value)) => MapChange::Added(value.clone()),
+                (None, None) => return None,
+            };
+            Some((id.clone(), change))
+        })
+        .collect()
+}
+
+/// Formats a service as `type (endpoint)`
+struct ServiceDisplay<'a>(&'a PlcService);
+
+impl Display for ServiceDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.0.r#type, self.0.endpoint)
+    }
+}
+
+impl Display for OperationChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationChange::RotationKeyAdded { key, index } => {
+                write!(f, "+ rotation key #{index}: {}", key.formatted_value())
+            }
+            OperationChange::RotationKeyRemoved { key, index } => {
+                write!(f, "- rotation key #{index}: {}", key.formatted_value())
+            }
+            OperationChange::RotationKeyMoved { key, from, to } => write!(
+                f,
+                "~ rotation key #{from} -> #{to}: {}",
+                key.formatted_value()
+            ),
+            OperationChange::VerificationMethodAdded { id, key } => {
+                write!(f, "+ verification method `{id}`: {}", key.formatted_value())
+            }
+            OperationChange::VerificationMethodRemoved { id, key } => {
+                write!(f, "- verification method `{id}`: {}", key.formatted_value())
+            }
+            OperationChange::VerificationMethodChanged { id, from, to } => write!(
+                f,
+                "~ verification method `{id}`: {} -> {}",
+                from.formatted_value(),
+                to.formatted_value()
+            ),
+            OperationChange::ServiceAdded { id, service } => {
+                write!(f, "+ service `{id}`: {}", ServiceDisplay(service))
+            }
+            OperationChange::ServiceRemoved { id, service } => {
+                write!(f, "- service `{id}`: {}", ServiceDisplay(service))
+            }
+            OperationChange::ServiceChanged { id, from, to } => write!(
+                f,
+                "~ service `{id}`: {} -> {}",
+                ServiceDisplay(from),
+                ServiceDisplay(to)
+            ),
+            OperationChange::AlsoKnownAsAdded { uri, index } => {
+                write!(f, "+ alias #{index}: {}", uri.as_str())
+            }
+            OperationChange::AlsoKnownAsRemoved { uri, index } => {
+                write!(f, "- alias #{index}: {}", uri.as_str())
+            }
+            OperationChange::AlsoKnownAsMoved { uri, from, to } => {
+                write!(f, "~ alias #{from} -> #{to}: {}", uri.as_str())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::test_util::random_did_key;
+
+    fn op(
+        rotation_keys: Vec<DidKey>,
+        verification_methods: &[(&str, &DidKey)],
+        also_known_as: &[&str],
+        services: &[(&str, &str)],
+    ) -> UnsignedPlcOperation {
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            rotation_keys,
+            verification_methods
+                .iter()
+                .map(|(id, key)| (id.to_string(), (*key).clone()))
+                .collect(),
+            also_known_as
+                .iter()
+                .map(|handle| AkaUri::new_at(handle).unwrap())
+                .collect(),
+            services
+                .iter()
+                .map(|(id, endpoint)| {
+                    (
+                        id.to_string(),
+                        PlcService::new_atproto_pds(endpoint).unwrap(),
+                    )
+                })
+                .collect(),
+        );
+        op
+    }
+
+    #[test]
+    fn identical() {
+        let [a, b] = [random_did_key(), random_did_key()];
+        let old = op(vec![a.clone(), b], &[("atproto", &a)], &["a.test"], &[]);
+        assert!(old.diff(&old.clone()).is_empty());
+    }
+
+    #[test]
+    fn rotation_key_changes() {
+        let [a, b, c] = [random_did_key(), random_did_key(), random_did_key()];
+        let old = op(vec![a.clone(), b.clone()], &[], &[], &[]);
+        let new = op(vec![c.clone(), b.clone()], &[], &[], &[]);
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                OperationChange::RotationKeyRemoved { key: a, index: 0 },
+                OperationChange::RotationKeyAdded { key: c, index: 0 },
+            ]
+        );
+
+        // Removing the highest-authority key promotes the other one
+        let promoted = op(vec![b.clone()], &[], &[], &[]);
+        let new_changes = new.diff(&promoted);
+        assert_matches!(
+            new_changes.as_slice(),
+            [
+                OperationChange::RotationKeyRemoved { index: 0, .. },
+                OperationChange::RotationKeyMoved { from: 1, to: 0, .. },
+            ]
+        );
+    }
+
+    #[test]
+    fn field_changes() {
+        let [a, b] = [random_did_key(), random_did_key()];
+        let old = op(
+            vec![a.clone()],
+            &[("atproto", &a), ("old", &a)],
+            &["a.test", "b.test"],
+            &[
+                ("atproto_pds", "https://old.test"),
+                ("gone", "https://gone.test"),
+            ],
+        );
+        let new = op(
+            vec![a.clone()],
+            &[("atproto", &b), ("new", &a)],
+            &["b.test", "a.test"],
+            &[("atproto_pds", "https://new.test")],
+        );
+
+        let changes: Vec<_> = old.diff(&new).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            vec![
+                format!(
+                    "~ verification method `atproto`: {} -> {}",
+                    a.formatted_value(),
+                    b.formatted_value()
+                ),
+                format!("+ verification method `new`: {}", a.formatted_value()),
+                format!("- verification method `old`: {}", a.formatted_value()),
+                "~ service `atproto_pds`: AtprotoPersonalDataServer (https://old.test) -> AtprotoPersonalDataServer (https://new.test)".to_string(),
+                "- service `gone`: AtprotoPersonalDataServer (https://gone.test)".to_string(),
+                "~ alias #0 -> #1: at://a.test".to_string(),
+                "~ alias #1 -> #0: at://b.test".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt::{Display, Formatter};
+
+use crate::aka_uri::AkaUri;
+use crate::did::AtprotoDid;
+use crate::did_plc::DidPlc;
+use crate::handle_resolver::{
+    resolve_handle, HandleResolution, HandleResolutionError, HandleResolver,
+};
+use crate::operation::unsigned::UnsignedPlcOperation;
+
+/// Whether a handle alias resolves back to the identity (see [UnsignedPlcOperation::verify_handles]).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HandleStatus {
+    /// The handle resolves to the identity's DID
+    Confirmed,
+    /// No resolver finds a DID for the handle
+    Broken,
+    /// Resolvers failed (e.g. a DNS or network error), and the others found nothing,
+    /// so it's unknown whether the handle resolves. Holds the error of each failed resolver.
+    Unresolved(Vec<(String, String)>),
+    /// The handle resolves to a different DID
+    PointsElsewhere(AtprotoDid),
+    /// Resolvers disagree on the DID, which makes the handle invalid
+    Conflict(Vec<(String, AtprotoDid)>),
+}
+
+/// The result of verifying a single `alsoKnownAs` handle.
+#[derive(Debug)]
+pub struct HandleVerification {
+    /// Position in `alsoKnownAs`
+    pub index: usize,
+    pub alias: AkaUri,
+    pub status: HandleStatus,
+    /// The individual answers, e.g. to explain a broken handle
+    pub resolution: HandleResolution,
+}
+
+impl UnsignedPlcOperation {
+    /// Resolves each handle in `alsoKnownAs` (in order), and checks whether it points back to
+    /// `did` - handles are only valid in both directions.
+    ///
+    /// DID aliases (e.g. `at://did:web:...`) are skipped.
+    pub fn verify_handles(
+        &self,
+        did: &DidPlc,
+        resolvers: &[&dyn HandleResolver],
+    ) -> Vec<HandleVerification> {
+        let did = AtprotoDid::Plc(did.clone());
+        self.also_known_as()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, alias)| {
+                let resolution = resolve_handle(alias.handle()?, resolvers)
+                    .expect("alsoKnownAs handles are validated when parsed");
+                let status = match resolution.did() {
+                    Ok(resolved) if *resolved == did => HandleStatus::Confirmed,
+                    Ok(resolved) => HandleStatus::PointsElsewhere(resolved.clone()),
+                    Err(HandleResolutionError::NotFound(_)) => {
+                        let errors: Vec<_> = resolution
+                            .answers()
+                            .iter()
+                            .filter_map(|(name, answer)| {
+                                Some((name.clone(), answer.as_ref().err()?.to_string()))
+                            })
+                            .collect();
+                        match errors.is_empty() {
+                            true => HandleStatus::Broken,
+                            false => HandleStatus::Unresolved(errors),
+                        }
+                    }
+                    Err(HandleResolutionError::Conflict { answers, .. }) => {
+                        HandleStatus::Conflict(answers)
+                    }
+                };
+                Some(HandleVerification {
+                    index,
+                    alias: alias.clone(),
+                    status,
+                    resolution,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Display for HandleStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandleStatus::Confirmed => f.write_str("resolves back to the DID"),
+            HandleStatus::Broken => f.write_str("doesn't resolve to any DID"),
+            HandleStatus::Unresolved(errors) => {
+                f.write_str("couldn't be resolved: ")?;
+                for (i, (name, error)) in errors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{error} ({name})")?;
+                }
+                Ok(())
+            }
+            HandleStatus::PointsElsewhere(did) => write!(f, "resolves to {did} instead"),
+            HandleStatus::Conflict(answers) => {
+                f.write_str("resolves to different DIDs: ")?;
+                for (i, (name, did)) in answers.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{did} ({name})")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::handle_resolver::{MemoryHandleResolver, ResolveError};
+
+    const DID: &str = "did:plc:c6te24qg5hx54qgegqylpqkx";
+    const OTHER_DID: &str = "did:plc:ewvi7nxzyoun6zhxrhs64oiz";
+
+    fn resolved(did: &str) -> AtprotoDid {
+        AtprotoDid::try_from(did).unwrap()
+    }
+
+    #[test]
+    fn alias_statuses() {
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            vec![],
+            IndexMap::new(),
+            [
+                "at://alice.test",
+                &format!("at://{OTHER_DID}"),
+                "at://broken.test",
+                "at://taken.test",
+                "at://split.test",
+            ]
+            .into_iter()
+            .map(|uri| AkaUri::try_from(uri).unwrap())
+            .collect(),
+            IndexMap::new(),
+        );
+        let dns = MemoryHandleResolver::new("dns")
+            .with_handle("alice.test", resolved(DID))
+            .with_handle("taken.test", resolved(OTHER_DID))
+            .with_handle("split.test", resolved(DID));
+        let https = MemoryHandleResolver::new("https")
+            .with_handle("split.test", resolved("did:web:split.test"));
+
+        let verifications = op.verify_handles(&DidPlc::try_from(DID).unwrap(), &[&dns, &https]);
+        let statuses: Vec<_> = verifications
+            .iter()
+            .map(|verification| (verification.index, verification.status.clone()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (0, HandleStatus::Confirmed),
+                (2, HandleStatus::Broken),
+                (3, HandleStatus::PointsElsewhere(resolved(OTHER_DID))),
+                (
+                    4,
+                    HandleStatus::Conflict(vec![
+                        ("dns".to_string(), resolved(DID)),
+                        ("https".to_string(), resolved("did:web:split.test")),
+                    ])
+                ),
+            ]
+        );
+    }
+
+    /// Fails for every handle, like an unreachable DNS server
+    struct FailingResolver;
+
+    impl HandleResolver for FailingResolver {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn resolve(&self, _handle: &str) -> Result<Option<AtprotoDid>, ResolveError> {
+            Err(ResolveError::MultipleDids(vec![
+                DID.to_string(),
+                OTHER_DID.to_string(),
+            ]))
+        }
+    }
+
+    #[test]
+    fn failing_resolver() {
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            vec![],
+            IndexMap::new(),
+            vec![
+                AkaUri::try_from("at://alice.test").unwrap(),
+                AkaUri::try_from("at://unknown.test").unwrap(),
+            ],
+            IndexMap::new(),
+        );
+        let https = MemoryHandleResolver::new("https").with_handle("alice.test", resolved(DID));
+
+        let verifications =
+            op.verify_handles(&DidPlc::try_from(DID).unwrap(), &[&FailingResolver, &https]);
+        assert_eq!(verifications[0].status, HandleStatus::Confirmed);
+        let status = &verifications[1].status;
+        assert_eq!(
+            *status,
+            HandleStatus::Unresolved(vec![(
+                "failing".to_string(),
+                format!("Multiple DIDs are claimed: {DID}, {OTHER_DID}")
+            )])
+        );
+        assert_eq!(
+            status.to_string(),
+            format!(
+                "couldn't be resolved: Multiple DIDs are claimed: {DID}, {OTHER_DID} (failing)"
+            )
+        );
+    }
+}
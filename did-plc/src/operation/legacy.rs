@@ -0,0 +1,255 @@
+use did_key::DidKey;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::aka_uri::{self, AkaUri};
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::plc_operation_ref::PlcOperationRef;
+use crate::plc_service::{PlcService, ServiceKind};
+
+/// Represents an unsigned legacy (v1) `create` genesis operation (all fields except for `sig`).
+///
+/// These can no longer be submitted, but older did:plc identities still start with one.
+/// The operation is normalized into the modern document shape when parsed
+/// (see [UnsignedLegacyCreate::normalized]).
+///
+/// Field order matters for `serde_json`, and matches the order
+/// used by [plc.directory](https://plc.directory).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "LegacyCreateFields", into = "LegacyCreateFields")]
+pub struct UnsignedLegacyCreate {
+    fields: LegacyCreateFields,
+    normalized: UnsignedPlcOperation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct LegacyCreateFields {
+    // Always null (None), legacy operations can only be genesis operations
+    prev: Option<PlcOperationRef>,
+
+    // Fixed value "create"
+    r#type: CreateType,
+
+    // Handle without the at:// prefix (e.g. "alice.bsky.social")
+    handle: String,
+
+    // PDS endpoint, may be missing the https:// prefix
+    service: String,
+
+    #[serde(rename = "signingKey")]
+    signing_key: DidKey,
+
+    #[serde(rename = "recoveryKey")]
+    recovery_key: DidKey,
+}
+
+const CREATE_TYPE: &str = "create";
+
+/// The `type` of [UnsignedLegacyCreate], only (de)serializes as "create"
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+enum CreateType {
+    #[serde(rename = "create")]
+    Create,
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum LegacyCreateError {
+    #[error("Legacy create operations must not have a `prev` reference")]
+    HasPrev,
+    #[error("Invalid handle: {0}")]
+    InvalidHandle(#[from] aka_uri::Error),
+}
+
+impl TryFrom<LegacyCreateFields> for UnsignedLegacyCreate {
+    type Error = LegacyCreateError;
+
+    fn try_from(fields: LegacyCreateFields) -> Result<Self, Self::Error> {
+        if fields.prev.is_some() {
+            return Err(LegacyCreateError::HasPrev);
+        }
+        let normalized = normalize(&fields)?;
+        Ok(Self { fields, normalized })
+    }
+}
+
+impl From<UnsignedLegacyCreate> for LegacyCreateFields {
+    fn from(op: UnsignedLegacyCreate) -> Self {
+        op.fields
+    }
+}
+
+/// Converts a legacy operation into the modern document shape, following the
+/// [reference implementation](https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/operations.ts):
+/// - `signingKey` becomes the `atproto` verification method
+/// - rotation keys are `[recoveryKey, signingKey]`, in this order
+/// - `handle` becomes the only `alsoKnownAs` entry (with an `at://` prefix)
+/// - `service` becomes the `atproto_pds` endpoint (with an `https://` prefix, if missing)
+fn normalize(fields: &LegacyCreateFields) -> Result<UnsignedPlcOperation, aka_uri::Error> {
+    let handle = &fields.handle;
+    let aka_uri = if handle.starts_with("at://") {
+        AkaUri::try_from(handle.as_str())?
+    } else {
+        AkaUri::new_at(
+            handle
+                .trim_start_matches("http://")
+                .trim_start_matches("https://"),
+        )?
+    };
+
+    let service = &fields.service;
+    let endpoint = if service.starts_with("http://") || service.starts_with("https://") {
+        service.clone()
+    } else {
+        format!("https://{service}")
+    };
+
+    let Ok(normalized) = UnsignedPlcOperation::new_genesis(
+        vec![fields.recovery_key.clone(), fields.signing_key.clone()],
+        IndexMap::from([("atproto".to_string(), fields.signing_key.clone())]),
+        vec![aka_uri],
+        IndexMap::from([(
+            "atproto_pds".to_string(),
+            // Kept as-is, even if it isn't a valid endpoint
+            PlcService {
+                r#type: ServiceKind::AtprotoPersonalDataServer,
+                endpoint,
+            },
+        )]),
+    );
+    Ok(normalized)
+}
+
+impl UnsignedLegacyCreate {
+    pub fn r#type(&self) -> &str {
+        CREATE_TYPE
+    }
+
+    /// The equivalent `plc_operation`. Note that this is not the signed data.
+    pub fn normalized(&self) -> &UnsignedPlcOperation {
+        &self.normalized
+    }
+
+    pub fn signing_key(&self) -> &DidKey {
+        &self.fields.signing_key
+    }
+
+    pub fn recovery_key(&self) -> &DidKey {
+        &self.fields.recovery_key
+    }
+
+    pub fn handle(&self) -> &str {
+        &self.fields.handle
+    }
+
+    pub fn service(&self) -> &str {
+        &self.fields.service
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::operation::{Signed, SignedOperation, UnsignedOperation};
+    use crate::test_util::{did_key, random_did_key, random_key};
+    use crate::{validate_operation_log, DidPlc};
+
+    fn legacy_json(signing_key: &DidKey, recovery_key: &DidKey) -> String {
+        format!(
+            r#"{{
+                "prev": null,
+                "type": "create",
+                "handle": "alice.bsky.social",
+                "service": "bsky.social",
+                "signingKey": "{}",
+                "recoveryKey": "{}"
+            }}"#,
+            signing_key.formatted_value(),
+            recovery_key.formatted_value()
+        )
+    }
+
+    #[test]
+    fn normalize_legacy_create() {
+        let signing_key = random_did_key();
+        let recovery_key = random_did_key();
+
+        let op: UnsignedLegacyCreate =
+            serde_json::from_str(&legacy_json(&signing_key, &recovery_key)).unwrap();
+        let normalized = op.normalized();
+
+        assert_eq!(
+            normalized.rotation_keys(),
+            &[recovery_key, signing_key.clone()]
+        );
+        assert_eq!(normalized.verification_methods()["atproto"], signing_key);
+        assert_eq!(
+            String::from(normalized.also_known_as()[0].clone()),
+            "at://alice.bsky.social"
+        );
+        assert_eq!(
+            normalized.services()["atproto_pds"].endpoint,
+            "https://bsky.social"
+        );
+        assert!(normalized.is_genesis());
+    }
+
+    #[test]
+    fn legacy_json_roundtrip() {
+        let json = legacy_json(&random_did_key(), &random_did_key());
+
+        let op: UnsignedOperation = serde_json::from_str(&json).unwrap();
+        assert_matches!(op, UnsignedOperation::LegacyCreate(_));
+
+        let reserialized = serde_json::to_value(&op).unwrap();
+        assert_eq!(
+            reserialized,
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn reject_legacy_prev() {
+        let json = r#"{
+            "prev": "bafyreihb7r2t3qegktlhxzqdr4gca77iov2w3putiiaut5qo33mj2ket2y",
+            "type": "create",
+            "handle": "alice.bsky.social",
+            "service": "https://bsky.social",
+            "signingKey": "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg",
+            "recoveryKey": "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK"
+        }"#;
+
+        assert!(serde_json::from_str::<UnsignedLegacyCreate>(json).is_err());
+    }
+
+    #[test]
+    fn validate_legacy_log() {
+        let signing_key = random_key();
+        let recovery_key = random_key();
+
+        let legacy_op: UnsignedLegacyCreate = serde_json::from_str(&legacy_json(
+            &did_key(&signing_key),
+            &did_key(&recovery_key),
+        ))
+        .unwrap();
+        let genesis: SignedOperation = Signed::new(legacy_op, &signing_key).into();
+        let did = DidPlc::from_signed_op(&genesis);
+
+        let normalized = genesis.normalized().unwrap();
+        let update = UnsignedPlcOperation::new(
+            normalized.rotation_keys().to_vec(),
+            normalized.verification_methods().clone(),
+            normalized.also_known_as().to_vec(),
+            normalized.services().clone(),
+            Some(genesis.get_cid_reference().unwrap()),
+        )
+        .unwrap()
+        .sign(&recovery_key)
+        .unwrap();
+
+        let report = validate_operation_log(&did, &[genesis, update.into()]).unwrap();
+        assert_eq!(report.signing_key_indices, vec![1, 0]);
+    }
+}
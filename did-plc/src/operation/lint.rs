@@ -0,0 +1,224 @@
+use did_key::DidKey;
+use thiserror::Error;
+
+use crate::operation::builder::ATPROTO_PDS_SERVICE_ID;
+use crate::operation::signed::SignedPlcOperation;
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::plc_operation_ref::PlcOperationRef;
+
+/// What is known about the signer, for [UnsignedPlcOperation::lint].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LintContext<'a> {
+    /// Rotation keys the signer holds the private keys of
+    pub owned_keys: &'a [DidKey],
+    /// The handle the account should have, if known
+    pub handle: Option<&'a str>,
+}
+
+/// A valid, but likely unintended (or dangerous) change.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum OperationLint {
+    #[error("None of your keys remain in the rotation keys, you won't be able to sign further operations")]
+    NoOwnedRotationKeys,
+    #[error(
+        "Rotation key `{}` (likely the PDS's key) is removed, but the PDS stays the same",
+        .0.formatted_value()
+    )]
+    PdsRotationKeyRemoved(Box<DidKey>),
+    #[error(
+        "`prev` is empty, but the identity already exists (this would be a new genesis operation)"
+    )]
+    MissingPrev,
+    #[error("`prev` ({actual}) doesn't reference the latest operation ({expected})")]
+    PrevMismatch {
+        expected: Box<PlcOperationRef>,
+        actual: Box<PlcOperationRef>,
+    },
+    #[error(
+        "The first alsoKnownAs entry is {}, but the handle is `{expected}`",
+        .actual.as_deref().unwrap_or("missing")
+    )]
+    HandleMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+    #[error("The {ATPROTO_PDS_SERVICE_ID} endpoint `{0}` is not https")]
+    InsecurePdsEndpoint(String),
+}
+
+impl UnsignedPlcOperation {
+    /// Checks the operation for footguns, relative to the latest operation of the identity
+    /// (`None` for a new identity).
+    ///
+    /// Rotation keys of `prev_op` which aren't owned (see [LintContext]) are assumed to belong
+    /// to the PDS. Unlike [Self::validate], these checks don't make plc.directory reject the
+    /// operation - which is exactly why they matter, since a bad operation can't be undone
+    /// once the recovery window passes (or at all, without an owned rotation key).
+    pub fn lint(
+        &self,
+        prev_op: Option<&SignedPlcOperation>,
+        context: &LintContext,
+    ) -> Vec<OperationLint> {
+        let mut lints = Vec::new();
+
+        if let Some(prev_op) = prev_op {
+            let owned = |key: &&DidKey| context.owned_keys.contains(key);
+            if prev_op.rotation_keys().iter().any(|key| owned(&key))
+                && !self.rotation_keys().iter().any(|key| owned(&key))
+            {
+                lints.push(OperationLint::NoOwnedRotationKeys);
+            }
+
+            if pds_endpoint(prev_op).is_some() && pds_endpoint(prev_op) == pds_endpoint(self) {
+                lints.extend(
+                    prev_op
+                        .rotation_keys()
+                        .iter()
+                        .filter(|key| !owned(key) && !self.rotation_keys().contains(key))
+                        .map(|key| OperationLint::PdsRotationKeyRemoved(Box::new(key.clone()))),
+                );
+            }
+
+            // The CID can't be computed for unserializable operations, which are caught elsewhere
+            if let Ok(expected) = prev_op.get_cid_reference() {
+                match self.prev() {
+                    None => lints.push(OperationLint::MissingPrev),
+                    Some(actual) if actual != expected => lints.push(OperationLint::PrevMismatch {
+                        expected: Box::new(expected),
+                        actual: Box::new(actual),
+                    }),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if let Some(handle) = context.handle {
+            let first_aka = self.also_known_as().first();
+            if first_aka.is_none_or(|aka| aka.handle() != Some(&handle.to_ascii_lowercase())) {
+                lints.push(OperationLint::HandleMismatch {
+                    expected: handle.to_string(),
+                    actual: first_aka.map(|aka| aka.as_str().to_string()),
+                });
+            }
+        }
+
+        if let Some(endpoint) = pds_endpoint(self) {
+            if !endpoint.starts_with("https://") {
+                lints.push(OperationLint::InsecurePdsEndpoint(endpoint.to_string()));
+            }
+        }
+
+        lints
+    }
+}
+
+fn pds_endpoint(op: &UnsignedPlcOperation) -> Option<&str> {
+    op.services()
+        .get(ATPROTO_PDS_SERVICE_ID)
+        .map(|service| service.endpoint.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::test_util::{did_key, random_did_key, random_keys};
+    use crate::{AkaUri, PlcOperationBuilder, PlcService, ServiceKind};
+
+    /// Latest operation with rotation keys `[pds_key, user_key]`, and the user's key
+    fn latest_op() -> (SignedPlcOperation, DidKey, DidKey) {
+        let keys: [_; 2] = random_keys();
+        let [pds_key, user_key] = keys.each_ref().map(did_key);
+        let op = UnsignedPlcOperation::new_genesis(
+            vec![pds_key.clone(), user_key.clone()],
+            IndexMap::new(),
+            vec![AkaUri::new_at("alice.test").unwrap()],
+            IndexMap::from([(
+                ATPROTO_PDS_SERVICE_ID.to_string(),
+                PlcService::new_atproto_pds("https://pds.test").unwrap(),
+            )]),
+        )
+        .unwrap()
+        .sign(&keys[0])
+        .unwrap();
+        (op, pds_key, user_key)
+    }
+
+    #[test]
+    fn safe_update() {
+        let (latest, _, user_key) = latest_op();
+        let next = PlcOperationBuilder::from_signed(&latest)
+            .unwrap()
+            .add_handle("alias.test")
+            .unwrap()
+            .build()
+            .unwrap();
+        let context = LintContext {
+            owned_keys: &[user_key],
+            handle: Some("alice.test"),
+        };
+
+        assert_eq!(next.lint(Some(&latest), &context), vec![]);
+    }
+
+    #[test]
+    fn key_lockout() {
+        let (latest, pds_key, user_key) = latest_op();
+        let next = PlcOperationBuilder::from_signed(&latest)
+            .unwrap()
+            .remove_rotation_key(&user_key)
+            .unwrap()
+            .remove_rotation_key(&pds_key)
+            .unwrap()
+            .insert_rotation_key(0, random_did_key())
+            .unwrap()
+            .build()
+            .unwrap();
+        let context = LintContext {
+            owned_keys: &[user_key],
+            handle: None,
+        };
+
+        assert_eq!(
+            next.lint(Some(&latest), &context),
+            vec![
+                OperationLint::NoOwnedRotationKeys,
+                OperationLint::PdsRotationKeyRemoved(Box::new(pds_key)),
+            ]
+        );
+    }
+
+    #[test]
+    fn cleared_prev_and_insecure_pds() {
+        let (latest, _, user_key) = latest_op();
+        let Ok(next) = UnsignedPlcOperation::new_genesis(
+            latest.rotation_keys().to_vec(),
+            IndexMap::new(),
+            vec![],
+            IndexMap::from([(
+                ATPROTO_PDS_SERVICE_ID.to_string(),
+                PlcService {
+                    r#type: ServiceKind::AtprotoPersonalDataServer,
+                    endpoint: "http://pds.test".to_string(),
+                },
+            )]),
+        );
+        let context = LintContext {
+            owned_keys: &[user_key],
+            handle: Some("alice.test"),
+        };
+
+        let lints = next.lint(Some(&latest), &context);
+        assert_matches!(
+            lints.as_slice(),
+            [
+                OperationLint::MissingPrev,
+                OperationLint::HandleMismatch { actual: None, .. },
+                OperationLint::InsecurePdsEndpoint(_),
+            ]
+        );
+    }
+}
@@ -1,5 +1,21 @@
+mod builder;
+mod constraints;
+mod diff;
+mod handle_verification;
+mod legacy;
+mod lint;
+mod recovery;
 mod signed;
+mod tombstone;
 mod unsigned;
 
+pub use builder::*;
+pub use constraints::*;
+pub use diff::*;
+pub use handle_verification::*;
+pub use legacy::*;
+pub use lint::*;
+pub use recovery::*;
 pub use signed::*;
+pub use tombstone::*;
 pub use unsigned::*;
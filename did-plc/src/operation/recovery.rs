@@ -0,0 +1,308 @@
+use chrono::{DateTime, Utc};
+use did_key::DidKey;
+use thiserror::Error;
+
+use crate::audit_log::AuditLog;
+use crate::operation::signed::VerificationError;
+use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::PlcOperationRef;
+
+#[derive(Error, Debug)]
+pub enum RecoveryError {
+    #[error("Operation `{0}` is not an active operation in the audit log")]
+    OperationNotFound(Box<PlcOperationRef>),
+    #[error("The genesis operation cannot be overridden")]
+    CannotOverrideGenesis,
+    #[error("Key `{0}` is not a rotation key of the operation preceding the overridden one")]
+    NotARotationKey(String),
+    #[error(
+        "Key (index {key_index}) does not have a higher priority than the key which signed \
+        the overridden operation (index {disputed_key_index})"
+    )]
+    InsufficientKeyPriority {
+        key_index: usize,
+        disputed_key_index: usize,
+    },
+    #[error("The recovery window closed at {closed_at}")]
+    RecoveryWindowClosed { closed_at: DateTime<Utc> },
+    #[error("Cannot fork from a tombstone")]
+    CannotForkFromTombstone,
+    #[error("The overridden operation has an invalid signature: {0}")]
+    InvalidSignature(#[from] VerificationError),
+    #[error("The audit log has no active operation other than the genesis operation")]
+    NothingToOverride,
+}
+
+impl RecoveryError {
+    /// How far [UnsignedPlcOperation::new_recovery] got before failing, following the order
+    /// of its checks, so that the most relevant error can be reported for a whole audit log
+    fn progress(&self) -> u8 {
+        match self {
+            RecoveryError::NothingToOverride => 0,
+            RecoveryError::OperationNotFound(_) => 1,
+            RecoveryError::CannotOverrideGenesis => 2,
+            RecoveryError::CannotForkFromTombstone => 3,
+            RecoveryError::NotARotationKey(_) => 4,
+            RecoveryError::InvalidSignature(_) => 5,
+            RecoveryError::InsufficientKeyPriority { .. } => 6,
+            RecoveryError::RecoveryWindowClosed { .. } => 7,
+        }
+    }
+}
+
+impl UnsignedPlcOperation {
+    /// Creates an operation which overrides (nullifies) `overridden` and all later operations.
+    ///
+    /// The new operation forks from the operation preceding `overridden`: it copies its state
+    /// and references it via `prev`. The result must be signed by `recovery_key`, which has to
+    /// be a rotation key of that preceding operation, with a higher priority (lower index)
+    /// than the key which signed `overridden`. The operation must be published within the
+    /// recovery window (see [crate::RECOVERY_WINDOW]) of `overridden`, which is checked
+    /// against `now`.
+    ///
+    /// The copied state can still be modified (e.g. to remove a compromised key) before signing,
+    /// see [crate::PlcOperationBuilder::edit].
+    pub fn new_recovery(
+        audit_log: &AuditLog,
+        overridden: PlcOperationRef,
+        recovery_key: &DidKey,
+        now: DateTime<Utc>,
+    ) -> Result<Self, RecoveryError> {
+        let (overridden_index, overridden_entry) = audit_log
+            .active_entries()
+            .find(|(_, entry)| entry.cid == overridden)
+            .ok_or_else(|| RecoveryError::OperationNotFound(Box::new(overridden)))?;
+
+        let fork_point = overridden_entry
+            .operation
+            .prev()
+            .ok_or(RecoveryError::CannotOverrideGenesis)?;
+        let (_, fork_entry) = audit_log
+            .active_entries()
+            .take_while(|(index, _)| *index < overridden_index)
+            .find(|(_, entry)| entry.cid == fork_point)
+            .ok_or_else(|| RecoveryError::OperationNotFound(Box::new(fork_point)))?;
+        let fork_op = fork_entry
+            .operation
+            .normalized()
+            .ok_or(RecoveryError::CannotForkFromTombstone)?;
+
+        let key_index = fork_op
+            .rotation_keys()
+            .iter()
+            .position(|key| key == recovery_key)
+            .ok_or_else(|| RecoveryError::NotARotationKey(recovery_key.formatted_value().into()))?;
+        let disputed_key_index = overridden_entry.operation.verify(fork_op.rotation_keys())?;
+        if key_index >= disputed_key_index {
+            return Err(RecoveryError::InsufficientKeyPriority {
+                key_index,
+                disputed_key_index,
+            });
+        }
+
+        let closed_at = overridden_entry.recovery_window_closes();
+        if now > closed_at {
+            return Err(RecoveryError::RecoveryWindowClosed { closed_at });
+        }
+
+        let Ok(recovery_op) = UnsignedPlcOperation::new(
+            fork_op.rotation_keys().to_vec(),
+            fork_op.verification_methods().clone(),
+            fork_op.also_known_as().to_vec(),
+            fork_op.services().clone(),
+            Some(fork_point),
+        );
+        Ok(recovery_op)
+    }
+
+    /// Like [Self::new_recovery], but overrides the earliest active operation that
+    /// `recovery_key` may still override at `now`.
+    ///
+    /// Everything from that operation onwards is nullified - including legitimate operations
+    /// signed by lower-priority keys, so check the audit log before publishing.
+    ///
+    /// If no operation can be overridden, the error of the operation which got the furthest
+    /// is returned, e.g. [RecoveryError::RecoveryWindowClosed] rather than
+    /// [RecoveryError::NotARotationKey] if the key could have overridden an older operation.
+    pub fn new_recovery_with_key(
+        audit_log: &AuditLog,
+        recovery_key: &DidKey,
+        now: DateTime<Utc>,
+    ) -> Result<Self, RecoveryError> {
+        let mut error = RecoveryError::NothingToOverride;
+        for (_, entry) in audit_log.active_entries() {
+            if entry.operation.prev().is_none() {
+                continue;
+            }
+            match Self::new_recovery(audit_log, entry.cid, recovery_key, now) {
+                Ok(recovery_op) => return Ok(recovery_op),
+                Err(err) if err.progress() > error.progress() => error = err,
+                Err(_) => {}
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::test_util::{
+        created_at, did_key, fork_fixture, random_did_key, random_key, signed_op,
+    };
+    use crate::{PlcOperationBuilder, RECOVERY_WINDOW};
+
+    #[test]
+    fn recovery_op_is_accepted() {
+        let f = fork_fixture();
+        let now = f.created_at + TimeDelta::hours(1);
+
+        let recovery_op =
+            UnsignedPlcOperation::new_recovery(&f.log, f.update_ref, &did_key(&f.keys[1]), now)
+                .unwrap();
+        assert_eq!(
+            recovery_op.rotation_keys(),
+            f.log[0].operation.rotation_keys()
+        );
+        assert_eq!(recovery_op.prev(), Some(f.log[0].cid));
+
+        let resolution = f
+            .log
+            .resolve(&recovery_op.sign(&f.keys[1]).unwrap().into(), now)
+            .unwrap();
+        assert_eq!(resolution.nullified, vec![1]);
+    }
+
+    #[test]
+    fn reject_same_key() {
+        let f = fork_fixture();
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery(
+                &f.log,
+                f.update_ref,
+                &did_key(&f.keys[2]),
+                f.created_at
+            ),
+            Err(RecoveryError::InsufficientKeyPriority {
+                key_index: 2,
+                disputed_key_index: 2
+            })
+        );
+    }
+
+    #[test]
+    fn reject_unknown_key() {
+        let f = fork_fixture();
+        let unknown_key = random_key();
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery(
+                &f.log,
+                f.update_ref,
+                &did_key(&unknown_key),
+                f.created_at
+            ),
+            Err(RecoveryError::NotARotationKey(_))
+        );
+    }
+
+    #[test]
+    fn reject_genesis() {
+        let f = fork_fixture();
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery(
+                &f.log,
+                f.log[0].cid,
+                &did_key(&f.keys[0]),
+                f.created_at
+            ),
+            Err(RecoveryError::CannotOverrideGenesis)
+        );
+    }
+
+    #[test]
+    fn reject_late_recovery() {
+        let f = fork_fixture();
+        let now = f.created_at + RECOVERY_WINDOW + TimeDelta::seconds(1);
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery(&f.log, f.update_ref, &did_key(&f.keys[0]), now),
+            Err(RecoveryError::RecoveryWindowClosed { .. })
+        );
+    }
+
+    #[test]
+    fn find_overridden_operation() {
+        let f = fork_fixture();
+        let now = f.created_at + TimeDelta::hours(1);
+
+        let recovery_op =
+            UnsignedPlcOperation::new_recovery_with_key(&f.log, &did_key(&f.keys[1]), now).unwrap();
+        assert_eq!(recovery_op.prev(), Some(f.genesis_ref));
+    }
+
+    #[test]
+    fn find_overridden_operation_errors() {
+        let f = fork_fixture();
+        let now = f.created_at + TimeDelta::hours(1);
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery_with_key(&f.log, &random_did_key(), now),
+            Err(RecoveryError::NotARotationKey(_))
+        );
+        // The key which signed the update can't override it
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery_with_key(&f.log, &did_key(&f.keys[2]), now),
+            Err(RecoveryError::InsufficientKeyPriority {
+                key_index: 2,
+                disputed_key_index: 2
+            })
+        );
+        let late = f.created_at + RECOVERY_WINDOW + TimeDelta::seconds(1);
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery_with_key(&f.log, &did_key(&f.keys[0]), late),
+            Err(RecoveryError::RecoveryWindowClosed { .. })
+        );
+    }
+
+    #[test]
+    fn nothing_to_override() {
+        let key = random_key();
+        let genesis = signed_op(&[did_key(&key)], None, &key);
+        let mut log = AuditLog::default();
+        log.apply(&genesis.get_did_plc(), genesis, created_at())
+            .unwrap();
+
+        assert_matches!(
+            UnsignedPlcOperation::new_recovery_with_key(&log, &did_key(&key), created_at()),
+            Err(RecoveryError::NothingToOverride)
+        );
+    }
+
+    #[test]
+    fn edit_recovery_op() {
+        let f = fork_fixture();
+        let compromised_key = did_key(&f.keys[2]);
+
+        let recovery_op = UnsignedPlcOperation::new_recovery(
+            &f.log,
+            f.update_ref,
+            &did_key(&f.keys[0]),
+            f.created_at,
+        )
+        .unwrap();
+        let edited = PlcOperationBuilder::edit(&recovery_op)
+            .remove_rotation_key(&compromised_key)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(edited.prev(), recovery_op.prev());
+        assert_eq!(edited.rotation_keys(), &recovery_op.rotation_keys()[..2]);
+    }
+}
@@ -1,55 +1,75 @@
+use std::collections::TryReserveError;
+
 use base64::engine::general_purpose::GeneralPurpose;
-use base64::engine::GeneralPurposeConfig;
+use base64::engine::{DecodePaddingMode, GeneralPurposeConfig};
 use base64::{alphabet, Engine};
 use derive_more::Deref;
-use ecdsa::signature::Signer;
-use ecdsa::{Signature, SignatureEncoding};
+use did_key::{DecodedPublicKey, DidKey};
+use ecdsa::signature::digest::generic_array::ArrayLength;
+use ecdsa::signature::{Signer, Verifier};
+use ecdsa::{Signature, SignatureEncoding, SignatureSize, VerifyingKey};
 use elliptic_curve::{CurveArithmetic, PrimeCurve};
 use serde::{Deserialize, Serialize};
+use serde_ipld_dagcbor::EncodeError;
+use thiserror::Error;
 
 use crate::did_plc::DidPlc;
-use crate::operation::unsigned::UnsignedPlcOperation;
+use crate::operation::legacy::UnsignedLegacyCreate;
+use crate::operation::tombstone::UnsignedPlcTombstone;
+use crate::operation::unsigned::{UnsignedOperation, UnsignedPlcOperation};
 use crate::plc_operation_ref::Error;
 use crate::{PlcBlessedKeyCurve, PlcOperationRef};
-// TODO: validate dag-cbor max size to match plc.directory
-// https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
 
-/// Represents a signed PLC operation (unsigned operation + `sig`).
+/// Represents a signed operation (unsigned operation + `sig`).
 ///
 /// Field order matters for `serde_json`, and matches the order
 /// used by [plc.directory](https://plc.directory).
 #[derive(Debug, Serialize, Deserialize, Deref, Clone)]
-pub struct SignedPlcOperation {
+pub struct Signed<T> {
     sig: SignatureBase64Url,
     #[serde(flatten)]
     #[deref]
-    inner: UnsignedPlcOperation,
+    inner: T,
 }
 
-impl SignedPlcOperation {
-    pub fn new<S, C>(unsigned_op: UnsignedPlcOperation, signing_key: &S) -> Self
+/// A signed `plc_operation`.
+pub type SignedPlcOperation = Signed<UnsignedPlcOperation>;
+
+/// A signed `plc_tombstone`.
+pub type SignedPlcTombstone = Signed<UnsignedPlcTombstone>;
+
+/// A signed legacy `create` operation.
+pub type SignedLegacyCreate = Signed<UnsignedLegacyCreate>;
+
+/// Any signed operation which may appear in an operation log.
+pub type SignedOperation = Signed<UnsignedOperation>;
+
+impl<T: Serialize> Signed<T> {
+    /// Signs the operation, always with a low-S signature (plc.directory rejects high-S ones,
+    /// but unlike secp256k1, the P-256 signer doesn't normalize them).
+    pub fn new<S, C>(unsigned_op: T, signing_key: &S) -> Self
     where
         // Curve C must be "blessed" (allowed by spec), and Signing key S must sign with curve C
         C: PlcBlessedKeyCurve,
         C: PrimeCurve + CurveArithmetic,
         S: Signer<Signature<C>>,
         Signature<C>: SignatureEncoding,
+        SignatureSize<C>: ArrayLength<u8>,
     {
         let unsigned_op_serialized = serde_ipld_dagcbor::ser::to_vec(&unsigned_op)
             .expect("Unsigned operation serialization failed");
 
         let signature: Signature<_> = Signer::sign(signing_key, &unsigned_op_serialized);
+        let signature = signature.normalize_s().unwrap_or(signature);
 
-        // PLC Directory does not use padding (trailing '=')
-        let encoding_base64url_no_pad = GeneralPurpose::new(
-            &alphabet::URL_SAFE,
-            GeneralPurposeConfig::new().with_encode_padding(false),
-        );
-        let signature_base64url = encoding_base64url_no_pad.encode(signature.to_bytes().as_ref());
+        Self::from_signature_bytes(unsigned_op, signature.to_bytes().as_ref())
+    }
 
-        SignedPlcOperation {
+    /// Attaches a raw (r||s) signature of the operation's dag-cbor encoding.
+    pub(crate) fn from_signature_bytes(unsigned_op: T, sig_bytes: &[u8]) -> Self {
+        Signed {
             inner: unsigned_op,
-            sig: SignatureBase64Url(signature_base64url),
+            sig: SignatureBase64Url(BASE64URL_NO_PAD.encode(sig_bytes)),
         }
     }
 
@@ -58,60 +78,626 @@ impl SignedPlcOperation {
     }
 
     pub fn get_cid_reference(&self) -> Result<PlcOperationRef, Error> {
-        PlcOperationRef::from_signed_op(&self)
+        PlcOperationRef::from_signed_op(self)
+    }
+
+    /// Verifies the signature against a set of rotation keys.
+    ///
+    /// For a genesis operation, these are its own rotation keys; otherwise, they should
+    /// be the rotation keys of the operation referenced by `prev`.
+    ///
+    /// Returns the index of the first key that produced the signature. Like plc.directory,
+    /// only accepts strictly encoded (see [SignatureBase64Url::decode]), low-S signatures.
+    pub fn verify(&self, rotation_keys: &[DidKey]) -> Result<usize, VerificationError> {
+        let unsigned_op_serialized = serde_ipld_dagcbor::ser::to_vec(&self.inner)?;
+        self.verify_serialized(&unsigned_op_serialized, rotation_keys)
+    }
+
+    /// Like [Self::verify], with the dag-cbor encoding of the unsigned operation
+    /// (see [Self::to_dag_cbor]).
+    pub(crate) fn verify_serialized(
+        &self,
+        unsigned_op_serialized: &[u8],
+        rotation_keys: &[DidKey],
+    ) -> Result<usize, VerificationError> {
+        let sig_bytes = self.sig.decode()?;
+
+        for (index, key) in rotation_keys.iter().enumerate() {
+            match verify_signature(key, unsigned_op_serialized, &sig_bytes) {
+                Some(true) => return Ok(index),
+                Some(false) => return Err(VerificationError::HighS { key_index: index }),
+                None => {}
+            }
+        }
+        Err(VerificationError::NoMatchingKey {
+            key_count: rotation_keys.len(),
+        })
+    }
+
+    /// The dag-cbor encodings of the unsigned and the signed operation.
+    ///
+    /// The operation is only encoded once: dag-cbor sorts map keys by length first,
+    /// so `sig` is usually the first entry, and can be prepended to the unsigned entries.
+    pub(crate) fn to_dag_cbor(&self) -> Result<(Vec<u8>, Vec<u8>), EncodeError<TryReserveError>> {
+        let unsigned = serde_ipld_dagcbor::ser::to_vec(&self.inner)?;
+        let signed = match self.prepend_sig(&unsigned) {
+            Some(signed) => signed,
+            // e.g. an unknown field with a short name
+            None => serde_ipld_dagcbor::ser::to_vec(self)?,
+        };
+        Ok((unsigned, signed))
+    }
+
+    /// The signed encoding, if `sig` belongs before all entries of the `unsigned` map
+    fn prepend_sig(&self, unsigned: &[u8]) -> Option<Vec<u8>> {
+        // Text key "sig", with its header
+        const SIG_KEY: &[u8] = &[0x63, b's', b'i', b'g'];
+        // Maps with up to 23 entries have a single header byte, holding the length
+        const MAP_HEADERS: std::ops::RangeInclusive<u8> = 0xa0..=0xb7;
+
+        let (&header, entries) = unsigned.split_first()?;
+        let signed_header = Some(header)
+            .filter(|header| MAP_HEADERS.contains(header))?
+            .checked_add(1)
+            .filter(|header| MAP_HEADERS.contains(header))?;
+        // Encoded keys compare bytewise in dag-cbor order (the header holds the length)
+        if entries
+            .get(..SIG_KEY.len())
+            .is_some_and(|key| key <= SIG_KEY)
+        {
+            return None;
+        }
+
+        let sig_value = serde_ipld_dagcbor::ser::to_vec(&self.sig.0).ok()?;
+        let mut signed = Vec::with_capacity(unsigned.len() + SIG_KEY.len() + sig_value.len());
+        signed.push(signed_header);
+        signed.extend_from_slice(SIG_KEY);
+        signed.extend_from_slice(&sig_value);
+        signed.extend_from_slice(entries);
+        Some(signed)
+    }
+
+    pub fn sig(&self) -> &SignatureBase64Url {
+        &self.sig
+    }
+
+    /// The unsigned part of the operation
+    pub fn unsigned(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl From<SignedPlcOperation> for SignedOperation {
+    fn from(signed_op: SignedPlcOperation) -> Self {
+        Signed {
+            sig: signed_op.sig,
+            inner: UnsignedOperation::Operation(signed_op.inner),
+        }
+    }
+}
+
+impl From<SignedPlcTombstone> for SignedOperation {
+    fn from(signed_op: SignedPlcTombstone) -> Self {
+        Signed {
+            sig: signed_op.sig,
+            inner: UnsignedOperation::Tombstone(signed_op.inner),
+        }
+    }
+}
+
+impl From<SignedLegacyCreate> for SignedOperation {
+    fn from(signed_op: SignedLegacyCreate) -> Self {
+        Signed {
+            sig: signed_op.sig,
+            inner: signed_op.inner.into(),
+        }
+    }
+}
+
+impl TryFrom<SignedOperation> for SignedPlcOperation {
+    type Error = SignedOperation;
+
+    /// Returns the original operation if it isn't a `plc_operation`.
+    fn try_from(signed_op: SignedOperation) -> Result<Self, Self::Error> {
+        match signed_op.inner {
+            UnsignedOperation::Operation(inner) => Ok(Signed {
+                sig: signed_op.sig,
+                inner,
+            }),
+            inner => Err(Signed {
+                sig: signed_op.sig,
+                inner,
+            }),
+        }
+    }
+}
+
+/// How to handle fields which aren't part of the known `plc_operation` format, e.g. if
+/// plc.directory adds one.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Reject operations with unknown fields
+    Strict,
+    /// Keep unknown fields (see [UnsignedPlcOperation::unknown_fields]), so that the operation
+    /// still hashes & verifies as signed. This is how operations always deserialize.
+    #[default]
+    Lenient,
+}
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Invalid operation JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Operation has unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+}
+
+impl SignedPlcOperation {
+    /// Parses a signed `plc_operation`, handling unknown fields according to `mode`.
+    pub fn from_json(json: &str, mode: ParseMode) -> Result<Self, ParseError> {
+        let plc_op: Self = serde_json::from_str(json)?;
+        match mode {
+            ParseMode::Strict if !plc_op.unknown_fields().is_empty() => Err(
+                ParseError::UnknownFields(plc_op.unknown_fields().keys().cloned().collect()),
+            ),
+            _ => Ok(plc_op),
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Checks whether `sig_bytes` (raw r||s) is a signature of `bytes` by `key`, in either S form.
+///
+/// Returns whether S is low, or `None` if the signature doesn't match.
+fn verify_signature(key: &DidKey, bytes: &[u8], sig_bytes: &[u8]) -> Option<bool> {
+    match key.public_key() {
+        DecodedPublicKey::Secp256k1(public_key) => {
+            verify_normalized(&VerifyingKey::from(public_key), bytes, sig_bytes)
+        }
+        DecodedPublicKey::NistP256(public_key) => {
+            verify_normalized(&VerifyingKey::from(public_key), bytes, sig_bytes)
+        }
+    }
+}
+
+// The secp256k1 verifier rejects high-S signatures outright, so verify the normalized form
+fn verify_normalized<C>(key: &VerifyingKey<C>, bytes: &[u8], sig_bytes: &[u8]) -> Option<bool>
+where
+    C: PrimeCurve + CurveArithmetic,
+    SignatureSize<C>: ArrayLength<u8>,
+    VerifyingKey<C>: Verifier<Signature<C>>,
+{
+    let signature = Signature::<C>::from_slice(sig_bytes).ok()?;
+    let normalized = signature.normalize_s();
+    key.verify(bytes, normalized.as_ref().unwrap_or(&signature))
+        .ok()
+        .map(|()| normalized.is_none())
+}
+
+#[derive(Error, Debug)]
+pub enum VerificationError {
+    #[error(transparent)]
+    InvalidEncoding(#[from] SignatureError),
+    #[error(transparent)]
+    Encode(#[from] EncodeError<TryReserveError>),
+    #[error("Signature does not match any of the {key_count} rotation keys")]
+    NoMatchingKey { key_count: usize },
+    #[error("Signature by rotation key {key_index} has a high S value (must be low-S)")]
+    HighS { key_index: usize },
+}
+
+// PLC Directory does not use padding (trailing '=')
+const BASE64URL_NO_PAD: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::RequireNone),
+);
+
+/// Length of a raw (r||s) signature, for both blessed curves
+pub const SIGNATURE_LENGTH: usize = 64;
+
+// First byte of a DER-encoded signature (a SEQUENCE)
+const DER_SEQUENCE_TAG: u8 = 0x30;
+
+/// The `sig` of an operation: an unpadded base64url encoding of a raw (r||s) signature.
+///
+/// The string is kept as-is (so that any operation log can be parsed), see [Self::decode].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct SignatureBase64Url(String);
 
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum SignatureError {
+    #[error("Signature must not be padded (trailing `=`)")]
+    Padded,
+    #[error("Signature must use the URL-safe base64 alphabet (`-_` instead of `+/`)")]
+    NotUrlSafe,
+    #[error("Signature is not valid unpadded base64url: {0}")]
+    InvalidBase64(#[from] base64::DecodeError),
+    #[error("Signature is DER-encoded, must be raw r||s")]
+    Der,
+    #[error("Signature must be {SIGNATURE_LENGTH} bytes (r||s), got {0}")]
+    InvalidLength(usize),
+    #[error("Signature r or s is out of range for the curve")]
+    OutOfRange,
+}
+
+impl SignatureBase64Url {
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LENGTH]) -> Self {
+        Self(BASE64URL_NO_PAD.encode(bytes))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Decodes the raw (r||s) signature, rejecting the encodings plc.directory refuses
+    /// (padding, the standard base64 alphabet, DER).
+    pub fn decode(&self) -> Result<[u8; SIGNATURE_LENGTH], SignatureError> {
+        if self.0.ends_with('=') {
+            return Err(SignatureError::Padded);
+        }
+        if self.0.contains(['+', '/']) {
+            return Err(SignatureError::NotUrlSafe);
+        }
+        let bytes = BASE64URL_NO_PAD.decode(&self.0)?;
+        bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| match bytes.as_slice() {
+                // A SEQUENCE spanning the rest of the bytes
+                [DER_SEQUENCE_TAG, length, ..] if *length as usize == bytes.len() - 2 => {
+                    SignatureError::Der
+                }
+                _ => SignatureError::InvalidLength(bytes.len()),
+            })
+    }
+
+    /// Whether S is in the lower half of the order of curve C (the only form plc.directory
+    /// accepts). A high-S signature is equally valid cryptographically, which makes it malleable.
+    pub fn is_low_s<C>(&self) -> Result<bool, SignatureError>
+    where
+        C: PlcBlessedKeyCurve + PrimeCurve + CurveArithmetic,
+        SignatureSize<C>: ArrayLength<u8>,
+    {
+        let signature =
+            Signature::<C>::from_slice(&self.decode()?).map_err(|_| SignatureError::OutOfRange)?;
+        Ok(signature.normalize_s().is_none())
+    }
+}
+
+impl TryFrom<String> for SignatureBase64Url {
+    type Error = SignatureError;
+
+    /// Only accepts signatures which [decode](Self::decode)
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let signature = Self(value);
+        signature.decode()?;
+        Ok(signature)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::assert_matches;
     use std::io::BufWriter;
 
+    use ecdsa::SigningKey;
+    use k256::Secp256k1;
+    use p256::NistP256;
+
     use super::*;
+    use crate::test_util::{random_key, PLC_OP_JSON};
+    use crate::ConstraintViolation;
 
-    #[test]
-    pub fn json_serde_matches() {
-        let plc_op_json = r#"
+    // Multiple map entries, deliberately in non-alphabetical order to check that it is kept
+    const MULTI_ENTRY_PLC_OP_JSON: &str = r#"
         {
-            "sig": "MDnVsVKDj-X2iHDtt9bX4xN8yIFruMexTHGFeLczgJZv-RNErz_Kg0mQDhEjezX158cP5-XBHPZ1nQ1K44OEFQ",
-            "prev": "bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku",
+            "sig": "npdHHonOVsGhfKPYaDGbYneFT4MoNLIucsbrJTbfyRwThif6r28g6ZYWizW_CJoJqgMwVC-_xz8pFicExXkFMg",
+            "prev": null,
             "type": "plc_operation",
             "services": {
                 "atproto_pds": {
                     "type": "AtprotoPersonalDataServer",
-                    "endpoint": "https://magic.us-west.host.bsky.network"
+                    "endpoint": "https://pds.example.com"
+                },
+                "atproto_labeler": {
+                    "type": "AtprotoLabeler",
+                    "endpoint": "https://labeler.example.com"
+                },
+                "bsky_fg": {
+                    "type": "BskyFeedGenerator",
+                    "endpoint": "https://feed.example.com"
                 }
             },
-            "alsoKnownAs": [
-                "at://test.metaflame.dev",
-                "at://alt.test.metaflame.dev"
-            ],
-            "rotationKeys": [
-                "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg",
-                "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK",
-                "did:key:zQ3shb9nQ22CdsmTCKoeHnwTXXB9i12Uh2XT3vyCHhgaJWBUL"
-            ],
+            "alsoKnownAs": ["at://test.metaflame.dev"],
+            "rotationKeys": ["did:key:zQ3shPLyeMso9veEjD8LqZp1aood4PuCio5LkGzos7wet1wwm"],
             "verificationMethods": {
+                "atproto_label": "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK",
                 "atproto": "did:key:zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe"
             }
         }
         "#;
 
-        let mut reserialized = BufWriter::new(Vec::new());
+    #[test]
+    pub fn json_serde_matches() {
+        for plc_op_json in [PLC_OP_JSON, MULTI_ENTRY_PLC_OP_JSON] {
+            let mut reserialized = BufWriter::new(Vec::new());
+
+            let mut de_json = serde_json::Deserializer::from_str(plc_op_json);
+            let mut ser_json = serde_json::Serializer::new(&mut reserialized);
+
+            serde_transcode::transcode(&mut de_json, &mut ser_json).unwrap();
+
+            let plc_op: SignedPlcOperation = serde_json::de::from_str(plc_op_json).unwrap();
+            let plc_ser = serde_json::to_string(&plc_op).unwrap();
+
+            assert_eq!(
+                plc_ser,
+                String::from_utf8(reserialized.buffer().to_vec()).unwrap()
+            );
+        }
+    }
 
-        let mut de_json = serde_json::Deserializer::from_str(plc_op_json);
-        let mut ser_json = serde_json::Serializer::new(&mut reserialized);
+    #[test]
+    fn verify_multi_entry_signature() {
+        // Signed over the dag-cbor encoding, which sorts map entries
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(MULTI_ENTRY_PLC_OP_JSON).unwrap();
 
-        serde_transcode::transcode(&mut de_json, &mut ser_json).unwrap();
+        assert_matches!(plc_op.verify(plc_op.rotation_keys()), Ok(0));
+    }
 
-        let plc_op: SignedPlcOperation = serde_json::de::from_str(plc_op_json).unwrap();
-        let plc_ser = serde_json::to_string(&plc_op).unwrap();
+    #[test]
+    fn dag_cbor_ignores_map_order() {
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(MULTI_ENTRY_PLC_OP_JSON).unwrap();
+        let Ok(inner) = UnsignedPlcOperation::new(
+            plc_op.rotation_keys().to_vec(),
+            plc_op
+                .verification_methods()
+                .clone()
+                .into_iter()
+                .rev()
+                .collect(),
+            plc_op.also_known_as().to_vec(),
+            plc_op.services().clone().into_iter().rev().collect(),
+            plc_op.prev(),
+        );
+        let reordered = Signed {
+            sig: plc_op.sig.clone(),
+            inner,
+        };
 
+        assert_ne!(
+            serde_json::to_string(&plc_op).unwrap(),
+            serde_json::to_string(&reordered).unwrap()
+        );
+        assert_eq!(
+            serde_ipld_dagcbor::to_vec(&plc_op).unwrap(),
+            serde_ipld_dagcbor::to_vec(&reordered).unwrap()
+        );
         assert_eq!(
-            plc_ser,
-            String::from_utf8(reserialized.buffer().to_vec()).unwrap()
+            plc_op.get_cid_reference().unwrap(),
+            reordered.get_cid_reference().unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let json = PLC_OP_JSON.replacen(
+            r#""type": "plc_operation","#,
+            r#""type": "plc_operation", "futureField": {"nested": [1, "two"]},"#,
+            1,
+        );
+        let plc_op = SignedPlcOperation::from_json(&json, ParseMode::Lenient).unwrap();
+        let known_op = SignedPlcOperation::from_json(PLC_OP_JSON, ParseMode::Strict).unwrap();
+
+        assert_eq!(
+            plc_op.unknown_fields().keys().collect::<Vec<_>>(),
+            ["futureField"]
+        );
+        assert!(known_op.unknown_fields().is_empty());
+        // plc.directory wouldn't accept it, though
+        assert_eq!(
+            plc_op.validate(),
+            [ConstraintViolation::UnknownField("futureField".to_string())]
+        );
+        // The unknown field is part of what was signed, and thus of the CID
+        assert_ne!(
+            plc_op.get_cid_reference().unwrap(),
+            known_op.get_cid_reference().unwrap()
+        );
+        let reserialized: serde_json::Value = serde_json::to_value(&plc_op).unwrap();
+        assert_eq!(
+            reserialized,
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()
+        );
+
+        assert_matches!(
+            SignedPlcOperation::from_json(&json, ParseMode::Strict),
+            Err(ParseError::UnknownFields(fields)) if fields == ["futureField"]
+        );
+    }
+
+    #[test]
+    fn dag_cbor_encodes_once() {
+        fn check<T: Serialize>(signed_op: &Signed<T>) {
+            let (unsigned, signed) = signed_op.to_dag_cbor().unwrap();
+            assert_eq!(
+                unsigned,
+                serde_ipld_dagcbor::to_vec(signed_op.unsigned()).unwrap()
+            );
+            assert_eq!(signed, serde_ipld_dagcbor::to_vec(signed_op).unwrap());
+        }
+
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(PLC_OP_JSON).unwrap();
+        let tombstone =
+            UnsignedPlcTombstone::new(plc_op.get_cid_reference().unwrap()).sign(&random_key());
+        check(&plc_op);
+        check(&SignedOperation::from(tombstone));
+        for multi_entry_json in [
+            MULTI_ENTRY_PLC_OP_JSON.to_string(),
+            // Sorted before `sig`, so it can't be prepended
+            MULTI_ENTRY_PLC_OP_JSON.replacen(r#""prev""#, r#""ab": 1, "prev""#, 1),
+        ] {
+            check(&SignedPlcOperation::from_json(&multi_entry_json, ParseMode::Lenient).unwrap());
+        }
+    }
+
+    fn sample_unsigned_op() -> UnsignedPlcOperation {
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(PLC_OP_JSON).unwrap();
+        (*plc_op).clone()
+    }
+
+    #[test]
+    fn verify_directory_signature() {
+        // Operation from plc.directory, signed by a key which was kept in the rotation keys
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(PLC_OP_JSON).unwrap();
+
+        assert_matches!(plc_op.verify(plc_op.rotation_keys()), Ok(1));
+    }
+
+    #[test]
+    fn verify_own_signature() {
+        let mut rng = rand::rngs::OsRng;
+        let other_key = SigningKey::<Secp256k1>::random(&mut rng);
+        let signing_key = SigningKey::<NistP256>::random(&mut rng);
+
+        let signed_op = SignedPlcOperation::new(sample_unsigned_op(), &signing_key);
+
+        let rotation_keys: Vec<DidKey> = vec![
+            elliptic_curve::PublicKey::from(other_key.verifying_key()).into(),
+            elliptic_curve::PublicKey::from(signing_key.verifying_key()).into(),
+        ];
+        assert_matches!(signed_op.verify(&rotation_keys), Ok(1));
+    }
+
+    #[test]
+    fn verify_wrong_key() {
+        let mut rng = rand::rngs::OsRng;
+        let signing_key = SigningKey::<Secp256k1>::random(&mut rng);
+        let other_key = SigningKey::<Secp256k1>::random(&mut rng);
+
+        let signed_op = SignedPlcOperation::new(sample_unsigned_op(), &signing_key);
+
+        let rotation_keys: Vec<DidKey> =
+            vec![elliptic_curve::PublicKey::from(other_key.verifying_key()).into()];
+        assert_matches!(
+            signed_op.verify(&rotation_keys),
+            Err(VerificationError::NoMatchingKey { key_count: 1 })
+        );
+    }
+
+    #[test]
+    fn verify_tampered_op() {
+        let mut rng = rand::rngs::OsRng;
+        let signing_key = SigningKey::<Secp256k1>::random(&mut rng);
+        let rotation_keys: Vec<DidKey> =
+            vec![elliptic_curve::PublicKey::from(signing_key.verifying_key()).into()];
+
+        let signed_op = SignedPlcOperation::new(sample_unsigned_op(), &signing_key);
+        let mut tampered_op = SignedPlcOperation::new(
+            UnsignedPlcOperation::new_genesis(
+                rotation_keys.clone(),
+                Default::default(),
+                vec![],
+                Default::default(),
+            )
+            .unwrap(),
+            &signing_key,
+        );
+        tampered_op.sig = signed_op.sig.clone();
+
+        assert_matches!(
+            tampered_op.verify(&rotation_keys),
+            Err(VerificationError::NoMatchingKey { .. })
+        );
+    }
+
+    #[test]
+    fn verify_invalid_encoding() {
+        let mut plc_op: SignedPlcOperation = serde_json::de::from_str(PLC_OP_JSON).unwrap();
+        plc_op.sig = SignatureBase64Url("not+base64url=".to_string());
+
+        assert_matches!(
+            plc_op.verify(plc_op.rotation_keys()),
+            Err(VerificationError::InvalidEncoding(SignatureError::Padded))
+        );
+    }
+
+    #[test]
+    fn strict_signature_decoding() {
+        let plc_op: SignedPlcOperation = serde_json::de::from_str(PLC_OP_JSON).unwrap();
+        let bytes = plc_op.sig().decode().unwrap();
+        assert_eq!(&SignatureBase64Url::from_bytes(&bytes), plc_op.sig());
+        assert_eq!(plc_op.sig().is_low_s::<Secp256k1>(), Ok(true));
+
+        let standard_base64 = base64::engine::general_purpose::STANDARD;
+        let decode = |sig: String| SignatureBase64Url::try_from(sig);
+        assert_eq!(
+            decode(format!("{}==", plc_op.sig().as_str())),
+            Err(SignatureError::Padded)
+        );
+        assert_eq!(
+            decode(
+                standard_base64
+                    .encode(bytes)
+                    .trim_end_matches('=')
+                    .to_string()
+            ),
+            Err(SignatureError::NotUrlSafe)
+        );
+        let der = Signature::<Secp256k1>::from_slice(&bytes).unwrap().to_der();
+        assert_eq!(
+            decode(BASE64URL_NO_PAD.encode(der)),
+            Err(SignatureError::Der)
+        );
+        assert_eq!(
+            decode(BASE64URL_NO_PAD.encode(&bytes[..63])),
+            Err(SignatureError::InvalidLength(63))
+        );
+        assert_eq!(
+            SignatureBase64Url::from_bytes(&[0; SIGNATURE_LENGTH]).is_low_s::<NistP256>(),
+            Err(SignatureError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn p256_signatures_are_low_s() {
+        let signing_key = SigningKey::<NistP256>::random(&mut rand::rngs::OsRng);
+        let rotation_keys: Vec<DidKey> =
+            vec![elliptic_curve::PublicKey::from(signing_key.verifying_key()).into()];
+
+        // Without normalization, each of these signatures would be high-S with a chance of 1/2
+        for i in 0..16 {
+            let handle = format!("test-{i}.example.com");
+            let op = UnsignedPlcOperation::new_genesis(
+                rotation_keys.clone(),
+                Default::default(),
+                vec![crate::AkaUri::new_at(&handle).unwrap()],
+                Default::default(),
+            )
+            .unwrap();
+            let signed_op = SignedPlcOperation::new(op, &signing_key);
+            assert_eq!(signed_op.sig().is_low_s::<NistP256>(), Ok(true));
+            assert_matches!(signed_op.verify(&rotation_keys), Ok(0));
+        }
+    }
+
+    #[test]
+    fn verify_rejects_high_s() {
+        let signing_key = SigningKey::<NistP256>::random(&mut rand::rngs::OsRng);
+        let rotation_keys: Vec<DidKey> =
+            vec![elliptic_curve::PublicKey::from(signing_key.verifying_key()).into()];
+        let mut signed_op = SignedPlcOperation::new(sample_unsigned_op(), &signing_key);
+
+        let low_s = Signature::<NistP256>::from_slice(&signed_op.sig().decode().unwrap()).unwrap();
+        let (r, s) = low_s.split_scalars();
+        let high_s = Signature::<NistP256>::from_scalars(r, -*s).unwrap();
+        signed_op.sig = SignatureBase64Url::from_bytes(&high_s.to_bytes().into());
+
+        assert_eq!(signed_op.sig().is_low_s::<NistP256>(), Ok(false));
+        assert_matches!(
+            signed_op.verify(&rotation_keys),
+            Err(VerificationError::HighS { key_index: 0 })
         );
     }
 }
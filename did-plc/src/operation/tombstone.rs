@@ -0,0 +1,120 @@
+use ecdsa::signature::digest::generic_array::ArrayLength;
+use ecdsa::signature::Signer;
+use ecdsa::{Signature, SignatureEncoding, SignatureSize};
+use elliptic_curve::{CurveArithmetic, PrimeCurve};
+use serde::{Deserialize, Serialize};
+
+use crate::operation::signed::SignedPlcTombstone;
+use crate::plc_operation_ref::PlcOperationRef;
+use crate::PlcBlessedKeyCurve;
+
+/// Represents an unsigned PLC tombstone (all fields except for `sig`).
+///
+/// A tombstone permanently deactivates a did:plc, no operation may follow it
+/// (apart from a recovery fork nullifying the tombstone itself).
+///
+/// Field order matters for `serde_json`, see [crate::UnsignedPlcOperation].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnsignedPlcTombstone {
+    // CID Hash reference to previous operation, tombstones cannot be genesis operations
+    prev: PlcOperationRef,
+
+    // Fixed value "plc_tombstone"
+    r#type: TombstoneType,
+}
+
+const TOMBSTONE_TYPE: &str = "plc_tombstone";
+
+/// The `type` of [UnsignedPlcTombstone], only (de)serializes as "plc_tombstone"
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+enum TombstoneType {
+    #[serde(rename = "plc_tombstone")]
+    PlcTombstone,
+}
+
+impl UnsignedPlcTombstone {
+    pub fn new(prev: PlcOperationRef) -> Self {
+        UnsignedPlcTombstone {
+            prev,
+            r#type: TombstoneType::PlcTombstone,
+        }
+    }
+
+    pub fn sign<S, C>(self, signing_key: &S) -> SignedPlcTombstone
+    where
+        C: PlcBlessedKeyCurve,
+        C: PrimeCurve + CurveArithmetic,
+        S: Signer<Signature<C>>,
+        Signature<C>: SignatureEncoding,
+        SignatureSize<C>: ArrayLength<u8>,
+    {
+        SignedPlcTombstone::new(self, signing_key)
+    }
+
+    pub fn r#type(&self) -> &str {
+        TOMBSTONE_TYPE
+    }
+
+    pub fn prev(&self) -> PlcOperationRef {
+        self.prev // Copiable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use ecdsa::SigningKey;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::operation::{SignedOperation, UnsignedOperation};
+
+    const PREV: &str = "bafyreihb7r2t3qegktlhxzqdr4gca77iov2w3putiiaut5qo33mj2ket2y";
+
+    #[test]
+    fn serialize_tombstone() {
+        let signing_key = SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng);
+        let tombstone =
+            UnsignedPlcTombstone::new(PlcOperationRef::try_from(PREV).unwrap()).sign(&signing_key);
+
+        let json = serde_json::to_value(&tombstone).unwrap();
+        assert_eq!(json["type"], "plc_tombstone");
+        assert_eq!(json["prev"], PREV);
+        assert_eq!(json.as_object().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn sign_and_verify_tombstone() {
+        let signing_key = SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng);
+        let did_key = elliptic_curve::PublicKey::from(signing_key.verifying_key()).into();
+        let tombstone =
+            UnsignedPlcTombstone::new(PlcOperationRef::try_from(PREV).unwrap()).sign(&signing_key);
+
+        assert_matches!(tombstone.verify(&[did_key]), Ok(0));
+
+        // CID must not depend on the representation
+        let any_op = SignedOperation::from(tombstone.clone());
+        assert_eq!(
+            any_op.get_cid_reference().unwrap(),
+            tombstone.get_cid_reference().unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize_any_operation() {
+        let tombstone_json =
+            format!(r#"{{"sig": "AAAA", "prev": "{PREV}", "type": "plc_tombstone"}}"#);
+
+        let op: SignedOperation = serde_json::from_str(&tombstone_json).unwrap();
+        assert_matches!(op.unsigned(), UnsignedOperation::Tombstone(_));
+        assert!(op.rotation_keys().is_empty());
+    }
+
+    #[test]
+    fn reject_wrong_type() {
+        let json = format!(r#"{{"prev": "{PREV}", "type": "plc_operation"}}"#);
+
+        assert!(serde_json::from_str::<UnsignedPlcTombstone>(&json).is_err());
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
-
 use did_key::DidKey;
+use ecdsa::signature::digest::generic_array::ArrayLength;
 use ecdsa::signature::Signer;
-use ecdsa::{Signature, SignatureEncoding};
+use ecdsa::{Signature, SignatureEncoding, SignatureSize};
 use elliptic_curve::{CurveArithmetic, PrimeCurve};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::aka_uri::AkaUri;
+use crate::operation::constraints::InvalidOperation;
+use crate::operation::legacy::UnsignedLegacyCreate;
 use crate::operation::signed::SignedPlcOperation;
+use crate::operation::tombstone::UnsignedPlcTombstone;
 use crate::plc_operation_ref::PlcOperationRef;
 use crate::plc_service::PlcService;
 use crate::PlcBlessedKeyCurve;
@@ -16,26 +19,25 @@ use crate::PlcBlessedKeyCurve;
 ///
 /// Field order matters for `serde_json`, and matches the order
 /// used by [plc.directory](https://plc.directory).
+///
+/// `services` & `verificationMethods` keep their JSON order when parsed (insertion order
+/// otherwise), so re-serializing an operation reproduces it byte-for-byte. dag-cbor sorts the
+/// keys itself, so the order never affects signatures or CIDs.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UnsignedPlcOperation {
     // CID Hash reference to previous operation, null (None) for genesis operations
     prev: Option<PlcOperationRef>,
 
     // Fixed value "plc_operation"
-    r#type: String,
+    r#type: OperationType,
 
     // Key-value map of services, services must have a type and endpoint.
     // Endpoint must be a valid http(s)-prefixed url
     // Key is currently just "atproto_pds" for type "AtprotoPersonalDataServer"
-    // TODO validate max count
-    // TODO validate ID length
-    // TODO validate service type length
-    // TODO validate endpoint length
-    // https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
-    services: HashMap<String, PlcService>,
-
-    // Array of at:// handles
-    // TODO prevent duplicate entries
+    // Count, ID, type & endpoint lengths are limited (see `validate`)
+    services: IndexMap<String, PlcService>,
+
+    // Array of at:// handles, without duplicates (see `validate`)
     #[serde(rename = "alsoKnownAs")]
     also_known_as: Vec<AkaUri>,
 
@@ -44,17 +46,23 @@ pub struct UnsignedPlcOperation {
     rotation_keys: Vec<DidKey>,
 
     // Key-value map of verification methods (e.g. "atproto" & signing key)
-    // TODO validate ID length
+    // Count & ID length are limited (see `validate`)
     #[serde(rename = "verificationMethods")]
-    verification_methods: HashMap<String, DidKey>,
+    verification_methods: IndexMap<String, DidKey>,
+
+    // Any other fields (e.g. added to the format after this was written), kept so that a parsed
+    // operation still hashes & verifies as signed. Serialized after the known fields.
+    // See `ParseMode` to reject them instead.
+    #[serde(flatten)]
+    unknown_fields: IndexMap<String, serde_json::Value>,
 }
 
 impl UnsignedPlcOperation {
     pub fn new_genesis(
         rotation_keys: Vec<DidKey>,
-        verification_methods: HashMap<String, DidKey>,
+        verification_methods: IndexMap<String, DidKey>,
         also_known_as: Vec<AkaUri>,
-        services: HashMap<String, PlcService>,
+        services: IndexMap<String, PlcService>,
     ) -> Result<Self, !> {
         Self::new(
             rotation_keys,
@@ -67,47 +75,93 @@ impl UnsignedPlcOperation {
 
     pub fn new(
         rotation_keys: Vec<DidKey>,
-        verification_methods: HashMap<String, DidKey>,
+        verification_methods: IndexMap<String, DidKey>,
         also_known_as: Vec<AkaUri>,
-        services: HashMap<String, PlcService>,
+        services: IndexMap<String, PlcService>,
         prev: Option<PlcOperationRef>,
     ) -> Result<Self, !> {
         Ok(UnsignedPlcOperation {
-            r#type: "plc_operation".to_string(),
+            r#type: OperationType::PlcOperation,
             rotation_keys,
             verification_methods,
             also_known_as,
             services,
             prev,
+            unknown_fields: IndexMap::new(),
         })
     }
 
-    pub fn sign<S, C>(self, signing_key: &S) -> SignedPlcOperation
+    /// Signs the operation, if it satisfies the plc.directory constraints
+    /// and has valid service endpoints (see [Self::validate_for_signing]).
+    pub fn sign<S, C>(self, signing_key: &S) -> Result<SignedPlcOperation, InvalidOperation>
+    where
+        C: PlcBlessedKeyCurve,
+        C: PrimeCurve + CurveArithmetic,
+        S: Signer<Signature<C>>,
+        Signature<C>: SignatureEncoding,
+        SignatureSize<C>: ArrayLength<u8>,
+    {
+        let violations = self.validate_for_signing();
+        if !violations.is_empty() {
+            return Err(InvalidOperation(violations));
+        }
+        Ok(self.sign_unchecked(signing_key))
+    }
+
+    /// Signs the operation without checking the plc.directory constraints.
+    ///
+    /// plc.directory will reject the result if it is invalid.
+    pub fn sign_unchecked<S, C>(self, signing_key: &S) -> SignedPlcOperation
     where
         C: PlcBlessedKeyCurve,
         C: PrimeCurve + CurveArithmetic,
         S: Signer<Signature<C>>,
         Signature<C>: SignatureEncoding,
+        SignatureSize<C>: ArrayLength<u8>,
     {
         SignedPlcOperation::new(self, signing_key)
     }
 
     pub fn r#type(&self) -> &str {
-        &self.r#type
+        OPERATION_TYPE
     }
     pub fn rotation_keys(&self) -> &[DidKey] {
         &self.rotation_keys
     }
-    pub fn verification_methods(&self) -> &HashMap<String, DidKey> {
+    pub fn verification_methods(&self) -> &IndexMap<String, DidKey> {
         &self.verification_methods
     }
     pub fn also_known_as(&self) -> &[AkaUri] {
         &self.also_known_as
     }
-    pub fn services(&self) -> &HashMap<String, PlcService> {
+
+    /// The first handle in `alsoKnownAs`, which atproto uses as the account's handle
+    /// (other entries may be DIDs, or alternative handles).
+    pub fn primary_handle(&self) -> Option<&str> {
+        self.also_known_as.iter().find_map(AkaUri::handle)
+    }
+    pub fn services(&self) -> &IndexMap<String, PlcService> {
         &self.services
     }
 
+    /// Fields which aren't part of the known `plc_operation` format, in JSON order.
+    ///
+    /// Empty unless the operation was parsed, or they were set with [Self::with_unknown_fields].
+    pub fn unknown_fields(&self) -> &IndexMap<String, serde_json::Value> {
+        &self.unknown_fields
+    }
+
+    /// Replaces the fields which aren't part of the known `plc_operation` format.
+    ///
+    /// plc.directory rejects operations with such fields (see [Self::validate]).
+    pub fn with_unknown_fields(
+        mut self,
+        unknown_fields: IndexMap<String, serde_json::Value>,
+    ) -> Self {
+        self.unknown_fields = unknown_fields;
+        self
+    }
+
     pub fn prev(&self) -> Option<PlcOperationRef> {
         self.prev // Copiable
     }
@@ -117,6 +171,101 @@ impl UnsignedPlcOperation {
     }
 }
 
+const OPERATION_TYPE: &str = "plc_operation";
+
+/// The `type` of [UnsignedPlcOperation], only (de)serializes as "plc_operation"
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+enum OperationType {
+    #[serde(rename = "plc_operation")]
+    PlcOperation,
+}
+
+/// Any unsigned operation which may appear in an operation log.
+///
+/// The variant is determined by the `type` field.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+// Only rare variants are boxed: nearly all operations are `plc_operation`s, which are larger than
+// tombstones, but boxing them would add an allocation to almost every operation
+#[allow(clippy::large_enum_variant)]
+pub enum UnsignedOperation {
+    Operation(UnsignedPlcOperation),
+    Tombstone(UnsignedPlcTombstone),
+    LegacyCreate(Box<UnsignedLegacyCreate>),
+}
+
+impl UnsignedOperation {
+    pub fn r#type(&self) -> &str {
+        match self {
+            UnsignedOperation::Operation(op) => op.r#type(),
+            UnsignedOperation::Tombstone(op) => op.r#type(),
+            UnsignedOperation::LegacyCreate(op) => op.r#type(),
+        }
+    }
+
+    pub fn prev(&self) -> Option<PlcOperationRef> {
+        match self {
+            UnsignedOperation::Operation(op) => op.prev(),
+            UnsignedOperation::Tombstone(op) => Some(op.prev()),
+            UnsignedOperation::LegacyCreate(_) => None,
+        }
+    }
+
+    /// Rotation keys which may sign the next operation.
+    ///
+    /// Empty for tombstones, since no operation may follow them.
+    pub fn rotation_keys(&self) -> &[DidKey] {
+        match self.normalized() {
+            Some(op) => op.rotation_keys(),
+            None => &[],
+        }
+    }
+
+    pub fn as_plc_operation(&self) -> Option<&UnsignedPlcOperation> {
+        match self {
+            UnsignedOperation::Operation(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    /// The document state after this operation, in the modern `plc_operation` shape.
+    ///
+    /// Legacy `create` operations are normalized, tombstones have no state (`None`).
+    pub fn normalized(&self) -> Option<&UnsignedPlcOperation> {
+        match self {
+            UnsignedOperation::Operation(op) => Some(op),
+            UnsignedOperation::Tombstone(_) => None,
+            UnsignedOperation::LegacyCreate(op) => Some(op.normalized()),
+        }
+    }
+
+    pub fn is_tombstone(&self) -> bool {
+        matches!(self, UnsignedOperation::Tombstone(_))
+    }
+
+    pub fn is_genesis(&self) -> bool {
+        self.prev().is_none()
+    }
+}
+
+impl From<UnsignedPlcOperation> for UnsignedOperation {
+    fn from(op: UnsignedPlcOperation) -> Self {
+        UnsignedOperation::Operation(op)
+    }
+}
+
+impl From<UnsignedPlcTombstone> for UnsignedOperation {
+    fn from(op: UnsignedPlcTombstone) -> Self {
+        UnsignedOperation::Tombstone(op)
+    }
+}
+
+impl From<UnsignedLegacyCreate> for UnsignedOperation {
+    fn from(op: UnsignedLegacyCreate) -> Self {
+        UnsignedOperation::LegacyCreate(Box::new(op))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test_log::test]
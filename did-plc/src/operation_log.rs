@@ -0,0 +1,253 @@
+use thiserror::Error;
+
+use crate::operation::{SignedOperation, VerificationError};
+use crate::{plc_operation_ref, DidPlc, PlcOperationRef};
+
+/// Result of a successful operation log validation.
+#[derive(Debug, Clone)]
+pub struct OperationLogReport {
+    /// The did:plc derived from the genesis operation
+    pub did: DidPlc,
+    /// CID references of all operations, in log order
+    pub operation_refs: Vec<PlcOperationRef>,
+    /// For each operation, the index of the rotation key (of the previous operation)
+    /// which signed it. The genesis operation is signed by one of its own rotation keys.
+    pub signing_key_indices: Vec<usize>,
+}
+
+/// The first broken link found in an operation log.
+///
+/// All indices refer to positions in the validated operation slice.
+#[derive(Error, Debug)]
+pub enum OperationLogError {
+    #[error("Operation log is empty")]
+    Empty,
+    #[error("Genesis operation must not have a `prev` reference (was `{0}`)")]
+    GenesisHasPrev(Box<PlcOperationRef>),
+    #[error("Genesis operation hashes to `{actual}`, expected `{expected}`")]
+    DidMismatch { expected: DidPlc, actual: DidPlc },
+    #[error("Operation {index} has `prev` set to {actual:?}, expected `{expected}`")]
+    PrevMismatch {
+        index: usize,
+        expected: Box<PlcOperationRef>,
+        actual: Option<Box<PlcOperationRef>>,
+    },
+    #[error("Operation {index} follows a tombstone")]
+    OperationAfterTombstone { index: usize },
+    #[error("Operation {index} has an invalid signature: {source}")]
+    InvalidSignature {
+        index: usize,
+        source: VerificationError,
+    },
+    #[error("Failed to compute CID of operation {index}: {source}")]
+    Cid {
+        index: usize,
+        source: plc_operation_ref::Error,
+    },
+}
+
+impl OperationLogError {
+    /// Index of the offending operation, if the error concerns a specific one.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            OperationLogError::Empty => None,
+            OperationLogError::GenesisHasPrev(_) | OperationLogError::DidMismatch { .. } => Some(0),
+            OperationLogError::PrevMismatch { index, .. }
+            | OperationLogError::OperationAfterTombstone { index }
+            | OperationLogError::InvalidSignature { index, .. }
+            | OperationLogError::Cid { index, .. } => Some(*index),
+        }
+    }
+}
+
+/// Validates an ordered did:plc operation log (as returned by `/log`), starting with genesis.
+///
+/// Checks that:
+/// - the genesis operation has no `prev`, is signed by one of its own rotation keys,
+///   and hashes to `expected_did`
+/// - every later operation references its predecessor's CID in `prev`
+/// - every later operation is signed by one of the predecessor's rotation keys
+/// - no operation follows a tombstone
+pub fn validate_operation_log(
+    expected_did: &DidPlc,
+    operations: &[SignedOperation],
+) -> Result<OperationLogReport, OperationLogError> {
+    let Some(genesis) = operations.first() else {
+        return Err(OperationLogError::Empty);
+    };
+
+    if let Some(prev) = genesis.prev() {
+        return Err(OperationLogError::GenesisHasPrev(Box::new(prev)));
+    }
+
+    let did = DidPlc::from_signed_op(genesis);
+    if did != *expected_did {
+        return Err(OperationLogError::DidMismatch {
+            expected: expected_did.clone(),
+            actual: did,
+        });
+    }
+
+    let mut operation_refs = Vec::with_capacity(operations.len());
+    let mut signing_key_indices = Vec::with_capacity(operations.len());
+
+    for (index, op) in operations.iter().enumerate() {
+        let rotation_keys = match index.checked_sub(1) {
+            None => op.rotation_keys(),
+            Some(prev_index) => {
+                let expected = operation_refs[prev_index];
+                if op.prev() != Some(expected) {
+                    return Err(OperationLogError::PrevMismatch {
+                        index,
+                        expected: Box::new(expected),
+                        actual: op.prev().map(Box::new),
+                    });
+                }
+                if operations[prev_index].is_tombstone() {
+                    return Err(OperationLogError::OperationAfterTombstone { index });
+                }
+                operations[prev_index].rotation_keys()
+            }
+        };
+
+        let key_index = op
+            .verify(rotation_keys)
+            .map_err(|source| OperationLogError::InvalidSignature { index, source })?;
+        signing_key_indices.push(key_index);
+
+        let op_ref = op
+            .get_cid_reference()
+            .map_err(|source| OperationLogError::Cid { index, source })?;
+        operation_refs.push(op_ref);
+    }
+
+    Ok(OperationLogReport {
+        did,
+        operation_refs,
+        signing_key_indices,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use ecdsa::SigningKey;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::test_util::{did_key, random_keys, unsigned_op};
+    use crate::UnsignedPlcTombstone;
+
+    /// Genesis with keys `[a, b]`, followed by an update (signed by `b`) to keys `[c]`
+    fn sample_log() -> (Vec<SignedOperation>, [SigningKey<Secp256k1>; 3]) {
+        let keys = random_keys();
+        let genesis = unsigned_op(&[did_key(&keys[0]), did_key(&keys[1])], None)
+            .sign(&keys[0])
+            .unwrap();
+        let update = unsigned_op(
+            &[did_key(&keys[2])],
+            Some(genesis.get_cid_reference().unwrap()),
+        )
+        .sign(&keys[1])
+        .unwrap();
+        (vec![genesis.into(), update.into()], keys)
+    }
+
+    #[test]
+    fn valid_log() {
+        let (log, _) = sample_log();
+        let did = log[0].get_did_plc();
+
+        let report = validate_operation_log(&did, &log).expect("Valid log was rejected");
+        assert_eq!(report.did, did);
+        assert_eq!(report.signing_key_indices, vec![0, 1]);
+        assert_eq!(report.operation_refs.len(), 2);
+    }
+
+    #[test]
+    fn empty_log() {
+        let (log, _) = sample_log();
+        let did = log[0].get_did_plc();
+
+        assert_matches!(
+            validate_operation_log(&did, &[]),
+            Err(OperationLogError::Empty)
+        );
+    }
+
+    #[test]
+    fn did_mismatch() {
+        let (log, _) = sample_log();
+        let did = DidPlc::try_from("did:plc:c6te24qg5hx54qgegqylpqkx").unwrap();
+
+        assert_matches!(
+            validate_operation_log(&did, &log),
+            Err(OperationLogError::DidMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn broken_prev() {
+        let (mut log, keys) = sample_log();
+        let did = log[0].get_did_plc();
+        log[1] = unsigned_op(
+            &[did_key(&keys[2])],
+            Some(log[1].get_cid_reference().unwrap()),
+        )
+        .sign(&keys[1])
+        .unwrap()
+        .into();
+
+        let err = validate_operation_log(&did, &log).unwrap_err();
+        assert_matches!(err, OperationLogError::PrevMismatch { index: 1, .. });
+        assert_eq!(err.index(), Some(1));
+    }
+
+    #[test]
+    fn signed_by_new_key() {
+        // The update must be signed by the genesis rotation keys, not its own
+        let (mut log, keys) = sample_log();
+        let did = log[0].get_did_plc();
+        log[1] = unsigned_op(&[did_key(&keys[2])], log[1].prev())
+            .sign(&keys[2])
+            .unwrap()
+            .into();
+
+        assert_matches!(
+            validate_operation_log(&did, &log),
+            Err(OperationLogError::InvalidSignature { index: 1, .. })
+        );
+    }
+
+    #[test]
+    fn log_ending_in_tombstone() {
+        let (mut log, keys) = sample_log();
+        let did = log[0].get_did_plc();
+        let tombstone = UnsignedPlcTombstone::new(log[1].get_cid_reference().unwrap());
+        log.push(tombstone.sign(&keys[2]).into());
+
+        let report = validate_operation_log(&did, &log).expect("Valid log was rejected");
+        assert_eq!(report.signing_key_indices, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn operation_after_tombstone() {
+        let (mut log, keys) = sample_log();
+        let did = log[0].get_did_plc();
+        let tombstone = UnsignedPlcTombstone::new(log[1].get_cid_reference().unwrap());
+        log.push(tombstone.sign(&keys[2]).into());
+        let tombstone_ref = log[2].get_cid_reference().unwrap();
+        log.push(
+            unsigned_op(&[did_key(&keys[2])], Some(tombstone_ref))
+                .sign(&keys[2])
+                .unwrap()
+                .into(),
+        );
+
+        assert_matches!(
+            validate_operation_log(&did, &log),
+            Err(OperationLogError::OperationAfterTombstone { index: 3 })
+        );
+    }
+}
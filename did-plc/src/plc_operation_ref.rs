@@ -9,10 +9,10 @@ use serde_ipld_dagcbor::EncodeError;
 use sha2::Digest;
 use thiserror::Error;
 
+use crate::operation::Signed;
 use crate::plc_operation_ref::codes::{
     PLC_MULTIBASE_CODEC, PLC_MULTIBASE_ENCODING, PLC_MULTIHASH_CODE,
 };
-use crate::SignedPlcOperation;
 
 mod codes {
     use multibase::Base;
@@ -137,14 +137,18 @@ impl PlcOperationRef {
     pub fn cid(&self) -> &Cid {
         &self.0
     }
-    pub fn from_signed_op(plc_op: &SignedPlcOperation) -> Result<PlcOperationRef, Error> {
+    pub fn from_signed_op<T: Serialize>(plc_op: &Signed<T>) -> Result<PlcOperationRef, Error> {
         let bytes = serde_ipld_dagcbor::ser::to_vec(plc_op)?;
         Self::from_dag_cbor(&bytes)
     }
 
     pub fn from_dag_cbor(bytes: &[u8]) -> Result<PlcOperationRef, Error> {
-        let digest = sha2::Sha256::digest(bytes);
-        let hash = Multihash::<64>::wrap(PLC_MULTIHASH_CODE, &digest)?;
+        Self::from_sha256_digest(&sha2::Sha256::digest(bytes))
+    }
+
+    /// The reference to an operation, given the SHA-256 of its dag-cbor encoding
+    pub(crate) fn from_sha256_digest(digest: &[u8]) -> Result<PlcOperationRef, Error> {
+        let hash = Multihash::<64>::wrap(PLC_MULTIHASH_CODE, digest)?;
 
         Self::new(hash)
     }
@@ -152,12 +156,13 @@ impl PlcOperationRef {
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use multihash_codetable::{Code, MultihashDigest};
 
     use super::*;
     use crate::plc_operation_ref::codes::PLC_MULTIBASE_CODEC;
+    use crate::SignedPlcOperation;
 
     #[test]
     fn from_str() {
@@ -1,16 +1,194 @@
+use std::fmt::{Display, Formatter};
+
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+const ATPROTO_PDS_TYPE: &str = "AtprotoPersonalDataServer";
+const ATPROTO_LABELER_TYPE: &str = "AtprotoLabeler";
+const BSKY_FEED_GENERATOR_TYPE: &str = "BskyFeedGenerator";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PlcService {
-    pub r#type: String,
-    pub endpoint: String, // Not validated to be a URL (but should usually be a URL?)
+    pub r#type: ServiceKind,
+    /// Kept as-is (so that signed operations serialize the same way), see [Self::endpoint_url]
+    pub endpoint: String,
+}
+
+/// The `type` of a [PlcService]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ServiceKind {
+    AtprotoPersonalDataServer,
+    AtprotoLabeler,
+    BskyFeedGenerator,
+    /// Any other type, which isn't validated beyond the plc.directory constraints
+    Unknown(String),
+}
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    #[error("Invalid endpoint URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("{kind} endpoint must be https, not `{scheme}`")]
+    NotHttps { kind: ServiceKind, scheme: String },
+    #[error("{kind} endpoint must not have a {part} (only scheme, host and port)")]
+    UnexpectedPart {
+        kind: ServiceKind,
+        part: &'static str,
+    },
 }
 
 impl PlcService {
-    pub fn new_atproto_pds(pds_endpoint: String) -> Self {
-        PlcService {
-            r#type: "AtprotoPersonalDataServer".to_string(),
-            endpoint: pds_endpoint,
+    /// An `AtprotoPersonalDataServer` service, with a validated endpoint
+    pub fn new_atproto_pds(pds_endpoint: &str) -> Result<Self, Error> {
+        Self::new(ServiceKind::AtprotoPersonalDataServer, pds_endpoint)
+    }
+
+    /// An `AtprotoLabeler` service, with a validated endpoint
+    pub fn new_atproto_labeler(labeler_endpoint: &str) -> Result<Self, Error> {
+        Self::new(ServiceKind::AtprotoLabeler, labeler_endpoint)
+    }
+
+    /// A service with a validated endpoint (see [Self::endpoint_url]).
+    pub fn new(kind: ServiceKind, endpoint: &str) -> Result<Self, Error> {
+        let service = PlcService {
+            r#type: kind,
+            endpoint: endpoint.to_string(),
+        };
+        service.endpoint_url()?;
+        Ok(service)
+    }
+
+    /// Parses & validates the endpoint.
+    ///
+    /// Endpoints of the known atproto services must be https URLs with only a host (and port),
+    /// unknown service types only need a valid URL.
+    pub fn endpoint_url(&self) -> Result<Url, Error> {
+        let url = Url::parse(&self.endpoint)?;
+        if matches!(self.r#type, ServiceKind::Unknown(_)) {
+            return Ok(url);
+        }
+
+        let kind = || self.r#type.clone();
+        if url.scheme() != "https" {
+            return Err(Error::NotHttps {
+                kind: kind(),
+                scheme: url.scheme().to_string(),
+            });
+        }
+        let unexpected_part = if !url.username().is_empty() || url.password().is_some() {
+            Some("username or password")
+        } else if url.path() != "/" {
+            Some("path")
+        } else if url.query().is_some() {
+            Some("query")
+        } else if url.fragment().is_some() {
+            Some("fragment")
+        } else {
+            None
+        };
+        match unexpected_part {
+            Some(part) => Err(Error::UnexpectedPart { kind: kind(), part }),
+            None => Ok(url),
+        }
+    }
+}
+
+impl ServiceKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ServiceKind::AtprotoPersonalDataServer => ATPROTO_PDS_TYPE,
+            ServiceKind::AtprotoLabeler => ATPROTO_LABELER_TYPE,
+            ServiceKind::BskyFeedGenerator => BSKY_FEED_GENERATOR_TYPE,
+            ServiceKind::Unknown(r#type) => r#type,
+        }
+    }
+}
+
+impl From<String> for ServiceKind {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            ATPROTO_PDS_TYPE => ServiceKind::AtprotoPersonalDataServer,
+            ATPROTO_LABELER_TYPE => ServiceKind::AtprotoLabeler,
+            BSKY_FEED_GENERATOR_TYPE => ServiceKind::BskyFeedGenerator,
+            _ => ServiceKind::Unknown(value),
+        }
+    }
+}
+
+impl From<&str> for ServiceKind {
+    fn from(value: &str) -> Self {
+        Self::from(value.to_string())
+    }
+}
+
+impl From<ServiceKind> for String {
+    fn from(value: ServiceKind) -> Self {
+        match value {
+            ServiceKind::Unknown(r#type) => r#type,
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for ServiceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_serde() {
+        let json = r#"[{"type":"AtprotoLabeler","endpoint":"https://mod.test"},{"type":"Custom","endpoint":"x"}]"#;
+        let services: Vec<PlcService> = serde_json::from_str(json).unwrap();
+        assert_eq!(services[0].r#type, ServiceKind::AtprotoLabeler);
+        assert_eq!(
+            services[1].r#type,
+            ServiceKind::Unknown("Custom".to_string())
+        );
+        assert_eq!(serde_json::to_string(&services).unwrap(), json);
+    }
+
+    #[test]
+    fn endpoint_validation() {
+        let service = |kind: ServiceKind, endpoint: &str| PlcService::new(kind, endpoint);
+        let pds = || ServiceKind::AtprotoPersonalDataServer;
+
+        assert!(service(pds(), "https://pds.test").is_ok());
+        assert!(service(pds(), "https://pds.test:8443/").is_ok());
+        assert!(service(ServiceKind::from("Custom"), "wss://relay.test/sub?x=1").is_ok());
+
+        assert_eq!(
+            service(pds(), "pds.test"),
+            Err(Error::InvalidUrl(url::ParseError::RelativeUrlWithoutBase))
+        );
+        assert_eq!(
+            service(pds(), "https://"),
+            Err(Error::InvalidUrl(url::ParseError::EmptyHost))
+        );
+        assert_eq!(
+            service(ServiceKind::BskyFeedGenerator, "http://feed.test"),
+            Err(Error::NotHttps {
+                kind: ServiceKind::BskyFeedGenerator,
+                scheme: "http".to_string()
+            })
+        );
+        for (endpoint, part) in [
+            ("https://pds.test/xrpc", "path"),
+            ("https://pds.test?a=b", "query"),
+            ("https://pds.test#top", "fragment"),
+            ("https://user@pds.test", "username or password"),
+        ] {
+            assert_eq!(
+                service(pds(), endpoint),
+                Err(Error::UnexpectedPart { kind: pds(), part }),
+                "{endpoint}"
+            );
         }
     }
 }
@@ -0,0 +1,119 @@
+//! Keys & operations shared by the unit tests.
+
+use chrono::{DateTime, Utc};
+use did_key::DidKey;
+use ecdsa::SigningKey;
+use indexmap::IndexMap;
+use k256::Secp256k1;
+
+use crate::{AuditLog, PlcOperationRef, SignedOperation, UnsignedPlcOperation};
+
+/// A `plc_operation` from plc.directory, for `did:plc:c6te24qg5hx54qgegqylpqkx`.
+///
+/// Signed by the second rotation key, which the operation kept.
+pub const PLC_OP_JSON: &str = r#"{
+    "sig": "MDnVsVKDj-X2iHDtt9bX4xN8yIFruMexTHGFeLczgJZv-RNErz_Kg0mQDhEjezX158cP5-XBHPZ1nQ1K44OEFQ",
+    "prev": "bafyreieg4qrrfepem7fpnsurihrenghjjqn7ebx5kansmdizmcxsdvtfku",
+    "type": "plc_operation",
+    "services": {
+        "atproto_pds": {
+            "type": "AtprotoPersonalDataServer",
+            "endpoint": "https://magic.us-west.host.bsky.network"
+        }
+    },
+    "alsoKnownAs": [
+        "at://test.metaflame.dev",
+        "at://alt.test.metaflame.dev"
+    ],
+    "rotationKeys": [
+        "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg",
+        "did:key:zQ3shpKnbdPx3g3CmPf5cRVTPe1HtSwVn5ish3wSnDPQCbLJK",
+        "did:key:zQ3shb9nQ22CdsmTCKoeHnwTXXB9i12Uh2XT3vyCHhgaJWBUL"
+    ],
+    "verificationMethods": {
+        "atproto": "did:key:zQ3shTuHbPL5uNPWmz5Tf6W1EWrhjWnxsCxNx9C7SdKqL1JXe"
+    }
+}"#;
+
+pub fn random_key() -> SigningKey<Secp256k1> {
+    SigningKey::random(&mut rand::rngs::OsRng)
+}
+
+pub fn random_keys<const N: usize>() -> [SigningKey<Secp256k1>; N] {
+    std::array::from_fn(|_| random_key())
+}
+
+pub fn did_key(key: &SigningKey<Secp256k1>) -> DidKey {
+    elliptic_curve::PublicKey::from(key.verifying_key()).into()
+}
+
+/// The did:key of a new key, for when the private key isn't needed
+pub fn random_did_key() -> DidKey {
+    did_key(&random_key())
+}
+
+/// An operation with only rotation keys (and `prev`)
+pub fn unsigned_op(
+    rotation_keys: &[DidKey],
+    prev: Option<PlcOperationRef>,
+) -> UnsignedPlcOperation {
+    let Ok(op) = UnsignedPlcOperation::new(
+        rotation_keys.to_vec(),
+        IndexMap::new(),
+        vec![],
+        IndexMap::new(),
+        prev,
+    );
+    op
+}
+
+pub fn signed_op(
+    rotation_keys: &[DidKey],
+    prev: Option<PlcOperationRef>,
+    signing_key: &SigningKey<Secp256k1>,
+) -> SignedOperation {
+    unsigned_op(rotation_keys, prev)
+        .sign(signing_key)
+        .unwrap()
+        .into()
+}
+
+pub fn created_at() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+        .unwrap()
+        .to_utc()
+}
+
+pub struct ForkFixture {
+    pub log: AuditLog,
+    pub keys: [SigningKey<Secp256k1>; 3],
+    pub genesis_ref: PlcOperationRef,
+    pub update_ref: PlcOperationRef,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Genesis with keys `[a, b, c]`, followed by an update to `[c]`, signed by `c` (lowest
+/// priority) - so `a` & `b` may still override the update.
+pub fn fork_fixture() -> ForkFixture {
+    let keys = random_keys();
+    let rotation_keys: Vec<_> = keys.iter().map(did_key).collect();
+    let created_at = created_at();
+
+    let genesis = signed_op(&rotation_keys, None, &keys[0]);
+    let did = genesis.get_did_plc();
+    let genesis_ref = genesis.get_cid_reference().unwrap();
+    let update = signed_op(&rotation_keys[2..], Some(genesis_ref), &keys[2]);
+    let update_ref = update.get_cid_reference().unwrap();
+
+    let mut log = AuditLog::default();
+    log.apply(&did, genesis, created_at).unwrap();
+    log.apply(&did, update, created_at).unwrap();
+
+    ForkFixture {
+        log,
+        keys,
+        genesis_ref,
+        update_ref,
+        created_at,
+    }
+}
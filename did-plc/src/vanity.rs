@@ -0,0 +1,259 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use thiserror::Error;
+
+use crate::did_plc::{PLC_HASH_ALPHABET_CHARS, PLC_HASH_BASE32_LENGTH};
+use crate::{
+    DidPlc, InvalidOperation, PlcBlessedSigningKey, Signed, SignedPlcOperation,
+    UnsignedPlcOperation,
+};
+
+/// A requested prefix of the did:plc hash (the part after `did:plc:`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VanityPrefix(String);
+
+#[derive(Error, Debug)]
+pub enum VanityError {
+    #[error("Invalid prefix character `{0}` (must be one of a-z, 2-7)")]
+    InvalidPrefixChar(char),
+    #[error(
+        "Prefix is {length} characters long, but a did:plc hash only has {PLC_HASH_BASE32_LENGTH}"
+    )]
+    PrefixTooLong { length: usize },
+    #[error("Only genesis operations determine the did:plc")]
+    NotGenesis,
+    #[error(transparent)]
+    InvalidOperation(#[from] InvalidOperation),
+}
+
+impl VanityPrefix {
+    /// Validates a prefix, which is case-insensitive (did:plc hashes are lowercase base32).
+    pub fn new(prefix: &str) -> Result<Self, VanityError> {
+        let prefix = prefix.to_ascii_lowercase();
+        if let Some(invalid) = prefix
+            .chars()
+            .find(|c| !PLC_HASH_ALPHABET_CHARS.contains(*c))
+        {
+            return Err(VanityError::InvalidPrefixChar(invalid));
+        }
+        if prefix.len() > PLC_HASH_BASE32_LENGTH {
+            return Err(VanityError::PrefixTooLong {
+                length: prefix.len(),
+            });
+        }
+        Ok(Self(prefix))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn matches(&self, did: &DidPlc) -> bool {
+        did.hash_encoded().starts_with(&self.0)
+    }
+
+    /// Expected number of signatures until a match, since each base32 character
+    /// carries 5 uniformly random bits.
+    pub fn expected_attempts(&self) -> f64 {
+        32f64.powi(self.0.len() as i32)
+    }
+}
+
+/// Shared progress of a [find_vanity_did] search, which may be read from other threads.
+#[derive(Debug)]
+pub struct VanityProgress {
+    attempts: AtomicU64,
+    started: Instant,
+    expected_attempts: f64,
+}
+
+impl VanityProgress {
+    pub fn new(prefix: &VanityPrefix) -> Self {
+        Self {
+            attempts: AtomicU64::new(0),
+            started: Instant::now(),
+            expected_attempts: prefix.expected_attempts(),
+        }
+    }
+
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn attempts_per_second(&self) -> f64 {
+        self.attempts() as f64 / self.elapsed().as_secs_f64()
+    }
+
+    /// Probability that a match would have been found by now
+    pub fn success_probability(&self) -> f64 {
+        let attempts = self.attempts() as f64;
+        -f64::exp_m1(attempts * f64::ln_1p(-1.0 / self.expected_attempts))
+    }
+
+    /// Expected time until a match, at the current rate.
+    ///
+    /// Attempts are independent, so this doesn't shrink as the search goes on.
+    /// `None` until the rate is known.
+    pub fn expected_remaining(&self) -> Option<Duration> {
+        let rate = self.attempts_per_second();
+        (rate > 0.0).then(|| Duration::from_secs_f64(self.expected_attempts / rate))
+    }
+}
+
+/// Searches for a signature of a genesis operation whose did:plc starts with `prefix`.
+///
+/// ECDSA signatures use a nonce, so signing with fresh random nonces produces different
+/// (equally valid) signed operations, and therefore different DIDs - the operation and its keys
+/// stay the same. Signs on `threads` threads until a match is found, or `cancel` is set (`None`).
+pub fn find_vanity_did<K: PlcBlessedSigningKey + Sync + ?Sized>(
+    unsigned_op: &UnsignedPlcOperation,
+    signing_key: &K,
+    prefix: &VanityPrefix,
+    threads: NonZeroUsize,
+    progress: &VanityProgress,
+    cancel: &AtomicBool,
+) -> Result<Option<SignedPlcOperation>, VanityError> {
+    if !unsigned_op.is_genesis() {
+        return Err(VanityError::NotGenesis);
+    }
+    let violations = unsigned_op.validate_for_signing();
+    if !violations.is_empty() {
+        return Err(InvalidOperation(violations).into());
+    }
+
+    let unsigned_op_serialized = serde_ipld_dagcbor::ser::to_vec(unsigned_op)
+        .expect("Unsigned operation serialization failed");
+    let found = OnceLock::new();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.get() {
+            scope.spawn(|| {
+                let mut rng = StdRng::from_entropy();
+                while found.get().is_none() && !cancel.load(Ordering::Relaxed) {
+                    let sig_bytes =
+                        signing_key.sign_to_bytes_with_rng(&mut rng, &unsigned_op_serialized);
+                    progress.attempts.fetch_add(1, Ordering::Relaxed);
+
+                    let signed_op = Signed::from_signature_bytes(unsigned_op, &sig_bytes);
+                    if prefix.matches(&signed_op.get_did_plc()) {
+                        let _ = found.set(Signed::from_signature_bytes(
+                            unsigned_op.clone(),
+                            &sig_bytes,
+                        ));
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(found.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use ecdsa::SigningKey;
+    use indexmap::IndexMap;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::test_util::random_key;
+
+    fn genesis_op(key: &SigningKey<Secp256k1>) -> UnsignedPlcOperation {
+        let Ok(op) = UnsignedPlcOperation::new_genesis(
+            vec![key.as_did_key()],
+            IndexMap::new(),
+            vec![],
+            IndexMap::new(),
+        );
+        op
+    }
+
+    #[test]
+    fn prefix_validation() {
+        assert_eq!(VanityPrefix::new("MeTa").unwrap().as_str(), "meta");
+        assert_matches!(
+            VanityPrefix::new("c0de"),
+            Err(VanityError::InvalidPrefixChar('0'))
+        );
+        assert_matches!(
+            VanityPrefix::new(&"a".repeat(25)),
+            Err(VanityError::PrefixTooLong { length: 25 })
+        );
+        assert_eq!(VanityPrefix::new("ab").unwrap().expected_attempts(), 1024.0);
+    }
+
+    #[test]
+    fn finds_prefix() {
+        let key = random_key();
+        let unsigned_op = genesis_op(&key);
+        let prefix = VanityPrefix::new("m").unwrap();
+        let progress = VanityProgress::new(&prefix);
+
+        let signed_op = find_vanity_did(
+            &unsigned_op,
+            &key,
+            &prefix,
+            NonZeroUsize::new(2).unwrap(),
+            &progress,
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(signed_op.get_did_plc().hash_encoded().starts_with('m'));
+        assert_matches!(signed_op.verify(unsigned_op.rotation_keys()), Ok(0));
+        assert!(progress.attempts() > 0);
+    }
+
+    #[test]
+    fn cancelled_search() {
+        let key = random_key();
+        let prefix = VanityPrefix::new("aaaaaaaaaaaa").unwrap();
+
+        let result = find_vanity_did(
+            &genesis_op(&key),
+            &key,
+            &prefix,
+            NonZeroUsize::MIN,
+            &VanityProgress::new(&prefix),
+            &AtomicBool::new(true),
+        );
+        assert_matches!(result, Ok(None));
+    }
+
+    #[test]
+    fn rejects_updates() {
+        let key = random_key();
+        let genesis = genesis_op(&key).sign(&key).unwrap();
+        let Ok(update) = UnsignedPlcOperation::new(
+            vec![key.as_did_key()],
+            IndexMap::new(),
+            vec![],
+            IndexMap::new(),
+            Some(genesis.get_cid_reference().unwrap()),
+        );
+        let prefix = VanityPrefix::new("a").unwrap();
+
+        let result = find_vanity_did(
+            &update,
+            &key,
+            &prefix,
+            NonZeroUsize::MIN,
+            &VanityProgress::new(&prefix),
+            &AtomicBool::new(false),
+        );
+        assert_matches!(result, Err(VanityError::NotGenesis));
+    }
+}
@@ -7,7 +7,6 @@ use did_key::DidKey;
 use did_plc::{PlcBlessedSigningKey, PlcBlessedSigningKeyBox};
 use ecdsa::SigningKey;
 use egui::{CollapsingHeader, Color32, Modal, RichText, Ui};
-use itertools::Itertools;
 use k256::Secp256k1;
 use log::{error, info};
 use p256::NistP256;
@@ -50,7 +49,7 @@ impl KeyStoreInterface {
             };
             ui.text_edit_singleline(&mut self.key_store_dir_str);
         });
-        let keys_header = format!("Keys ({})", &self.store.loaded_keys.len());
+        let keys_header = format!("Keys ({})", self.store.loaded_keys.len());
         CollapsingHeader::new(keys_header)
             .id_salt(egui::Id::from("Keys collapsing header"))
             .show(ui, |ui| {
@@ -99,6 +98,11 @@ impl KeyStore {
             .find(|key_box| key_box.as_did_key() == *key)
     }
 
+    /// Saves a new key to the key store directory (it's loaded on the next refresh)
+    pub fn save_key(&self, key: &PlcBlessedSigningKeyBox) -> std::io::Result<()> {
+        save_key(key, &self.key_store_path)
+    }
+
     pub fn set_dir(&mut self, dir_str: impl Into<PathBuf>) {
         self.key_store_path = dir_str.into();
     }
@@ -207,7 +211,7 @@ impl KeyGeneratorInterface {
                 KeyType::NistP256 => SigningKey::<NistP256>::new_random(&mut rng).into(),
             };
 
-            match Self::save_key(&key, &key_store_path) {
+            match save_key(&key, key_store_path) {
                 Ok(()) => {
                     self.modal_open = false;
                     return Some(key);
@@ -220,10 +224,10 @@ impl KeyGeneratorInterface {
 
         None
     }
+}
 
-    fn save_key(key: &PlcBlessedSigningKeyBox, key_store_path: &Path) -> std::io::Result<()> {
-        let key_path = key_store_path.join(key.as_did_key().multibase_value());
-        info!("Saving key to {}", key_path.display());
-        key.write_to_file(&key_path)
-    }
+fn save_key(key: &PlcBlessedSigningKeyBox, key_store_path: &Path) -> std::io::Result<()> {
+    let key_path = key_store_path.join(key.as_did_key().multibase_value());
+    info!("Saving key to {}", key_path.display());
+    key.write_to_file(&key_path)
 }
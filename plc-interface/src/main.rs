@@ -1,5 +1,3 @@
-#![feature(never_type)]
-#![feature(trait_alias)]
 extern crate core;
 
 mod app;
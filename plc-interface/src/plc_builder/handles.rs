@@ -0,0 +1,133 @@
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use did_plc::{
+    DidPlc, DnsHandleResolver, HandleResolver, HandleStatus, HandleVerification,
+    UnsignedPlcOperation, WellKnownHandleResolver,
+};
+use egui::{RichText, Ui};
+use log::{error, warn};
+
+#[derive(Debug, Default, Clone)]
+pub struct HandleCheckInterface {
+    did: String,
+    /// The handle the account should have (may be empty)
+    expected_handle: String,
+    check: Option<HandleCheck>,
+}
+
+/// A check running (or finished) in the background
+#[derive(Debug, Clone)]
+struct HandleCheck {
+    did: DidPlc,
+    verifications: Arc<OnceLock<Vec<HandleVerification>>>,
+}
+
+impl HandleCheckInterface {
+    pub fn set_did(&mut self, did: &DidPlc) {
+        self.did = did.formatted_did();
+    }
+
+    /// The handle the account should have, as entered by the user
+    pub fn expected_handle(&self) -> Option<&str> {
+        Some(self.expected_handle.trim()).filter(|handle| !handle.is_empty())
+    }
+
+    /// Draws the DID & expected handle fields, and the results of the last check.
+    ///
+    /// Returns the DID when the user starts a check (see [Self::start]).
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<DidPlc> {
+        let mut start = None;
+        ui.horizontal(|ui| {
+            ui.label("DID:");
+            ui.text_edit_singleline(&mut self.did);
+            if ui.button("Verify handles").clicked() {
+                match DidPlc::try_from(self.did.trim()) {
+                    Ok(did) => start = Some(did),
+                    Err(err) => error!("Invalid DID: {err}"),
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Expected handle:");
+            ui.text_edit_singleline(&mut self.expected_handle);
+        });
+
+        let Some(check) = &self.check else {
+            return start;
+        };
+        let Some(verifications) = check.verifications.get() else {
+            ui.label(RichText::new("Resolving handles...").weak().italics());
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+            return start;
+        };
+        if verifications.is_empty() {
+            ui.label(RichText::new("[no handles]").weak().italics());
+        }
+        for verification in verifications {
+            let text = format!("{}: {}", verification.alias.as_str(), verification.status);
+            match verification.status {
+                HandleStatus::Confirmed => ui.label(text),
+                _ => ui.colored_label(ui.visuals().warn_fg_color, text),
+            };
+        }
+        start
+    }
+
+    /// Starts resolving the handles of `plc_op` in the background, via DNS & HTTPS.
+    pub fn start(&mut self, plc_op: UnsignedPlcOperation, did: DidPlc) {
+        let check = HandleCheck {
+            did,
+            verifications: Arc::new(OnceLock::new()),
+        };
+
+        let background = check.clone();
+        thread::spawn(move || {
+            let dns = DnsHandleResolver::from_system_conf()
+                .inspect_err(|err| warn!("Skipping DNS handle resolution: {err}"))
+                .ok();
+            let well_known = WellKnownHandleResolver::new();
+            let mut resolvers: Vec<&dyn HandleResolver> = vec![&well_known];
+            if let Some(dns) = &dns {
+                resolvers.insert(0, dns);
+            }
+
+            let verifications = plc_op.verify_handles(&background.did, &resolvers);
+            let _ = background.verifications.set(verifications);
+        });
+        self.check = Some(check);
+    }
+
+    /// A warning if the primary handle of `plc_op` doesn't resolve back to the DID
+    /// (or hasn't been checked).
+    ///
+    /// Genesis operations are skipped, since nothing can point to a new DID yet.
+    pub fn primary_handle_warning(&self, plc_op: &UnsignedPlcOperation) -> Option<String> {
+        let handle = plc_op.primary_handle()?;
+        if plc_op.is_genesis() {
+            return None;
+        }
+
+        let verification = self
+            .check
+            .as_ref()
+            .filter(|check| check.did.formatted_did() == self.did.trim())
+            .and_then(|check| check.verifications.get())
+            .and_then(|verifications| {
+                verifications
+                    .iter()
+                    .find(|verification| verification.alias.handle() == Some(handle))
+            });
+        match verification {
+            Some(verification) if verification.status == HandleStatus::Confirmed => None,
+            Some(verification) => Some(format!(
+                "The primary handle `{handle}` {}",
+                verification.status
+            )),
+            None => Some(format!(
+                "The primary handle `{handle}` hasn't been verified to resolve back to the DID"
+            )),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use did_key::DidKey;
+use did_plc::{PlcBlessedSigningKey, PlcBlessedSigningKeyBox, PlcService};
+use ecdsa::SigningKey;
+use egui::{RichText, Ui};
+use k256::Secp256k1;
+use log::{error, info};
+
+use crate::app::key_store::KeyStore;
+
+/// Preset for turning the identity into a labeler
+#[derive(Debug, Default, Clone)]
+pub struct LabelerInterface {
+    endpoint: String,
+    label_key: Option<DidKey>,
+}
+
+impl LabelerInterface {
+    /// Draws the endpoint field & label key selector.
+    ///
+    /// Returns the labeler endpoint & label signing key when the user applies the preset
+    /// (see [did_plc::PlcOperationBuilder::set_labeler]).
+    pub fn ui(&mut self, ui: &mut Ui, key_store: &KeyStore) -> Option<(String, DidKey)> {
+        ui.horizontal(|ui| {
+            ui.label("Endpoint:");
+            ui.text_edit_singleline(&mut self.endpoint);
+        });
+        if !self.endpoint.is_empty() {
+            if let Err(err) = PlcService::new_atproto_labeler(&self.endpoint) {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Label signing key:");
+            let selected_text = match &self.label_key {
+                Some(key) => RichText::new(key.formatted_value()).monospace(),
+                None => RichText::new("[none]").weak().italics(),
+            };
+            egui::ComboBox::from_id_salt("Label key selector")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for key in key_store.keys() {
+                        let key = key.as_did_key();
+                        let text = RichText::new(key.formatted_value()).monospace();
+                        ui.selectable_value(&mut self.label_key, Some(key), text);
+                    }
+                });
+            if ui.button("Generate new key").clicked() {
+                self.generate_key(key_store);
+            }
+        });
+
+        if !ui.button("Add labeler service & key").clicked() {
+            return None;
+        }
+        let Some(label_key) = self.label_key.clone() else {
+            error!("No label signing key selected");
+            return None;
+        };
+        Some((self.endpoint.clone(), label_key))
+    }
+
+    /// Generates a secp256k1 key (like the PDS's `atproto` key) & saves it to the key store
+    fn generate_key(&mut self, key_store: &KeyStore) {
+        let key: PlcBlessedSigningKeyBox =
+            SigningKey::<Secp256k1>::new_random(&mut rand::rngs::OsRng).into();
+        match key_store.save_key(&key) {
+            Ok(()) => {
+                info!("Refresh the key store to see the new label signing key");
+                self.label_key = Some(key.as_did_key());
+            }
+            Err(err) => error!("Failed to save label signing key: {err}"),
+        }
+    }
+}
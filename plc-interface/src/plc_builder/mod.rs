@@ -1,21 +1,29 @@
-use std::collections::HashMap;
-
 use anyhow::{anyhow, Context, Result};
 use did_key::DidKey;
-use did_plc::{PlcOperationRef, PlcService, SignedPlcOperation, UnsignedPlcOperation};
+use did_plc::{
+    LintContext, OperationLint, ParseMode, PlcBlessedSigningKeyBox, PlcOperationBuilder,
+    PlcOperationRef, PlcService, SignedPlcOperation, UnsignedPlcOperation,
+};
 use eframe::Storage;
 use egui::{RichText, Ui, ViewportCommand, Widget};
-use log::{error, info};
+use indexmap::IndexMap;
+use log::{error, info, warn};
 
 use crate::app::key_store::KeyStore;
 use crate::plc_builder::aka::AlsoKnownAsInterface;
+use crate::plc_builder::handles::HandleCheckInterface;
+use crate::plc_builder::labeler::LabelerInterface;
 use crate::plc_builder::rotation_keys::RotationKeySetInterface;
 use crate::plc_builder::services::ServicesInterface;
+use crate::plc_builder::vanity::VanityInterface;
 use crate::plc_builder::verification_methods::VerificationMethodsInterface;
 
 mod aka;
+mod handles;
+mod labeler;
 mod rotation_keys;
 mod services;
+mod vanity;
 mod verification_methods;
 
 #[derive(Default, Clone, Debug)]
@@ -25,10 +33,19 @@ pub struct PlcBuilderInterface {
     verification_methods: VerificationMethodsInterface,
     services: ServicesInterface,
     prev: Option<PlcOperationRef>,
+    // The latest operation of the identity, if it was loaded (e.g. from `log/last`).
+    // Kept when `prev` is cleared, so that the mistake can be pointed out
+    prev_op: Option<SignedPlcOperation>,
+    // Fields of the loaded operation which aren't part of the known format. They are kept in
+    // new operations (which plc.directory rejects) until the user drops them
+    unknown_fields: IndexMap<String, serde_json::Value>,
+    vanity: VanityInterface,
+    handles: HandleCheckInterface,
+    labeler: LabelerInterface,
 }
 
 impl PlcBuilderInterface {
-    pub(crate) fn save(&self, storage: &mut dyn Storage) {}
+    pub(crate) fn save(&self, _storage: &mut dyn Storage) {}
 }
 
 impl PlcBuilderInterface {
@@ -54,8 +71,15 @@ impl PlcBuilderInterface {
                 *self = Self::new_with_defaults()
             }
 
+            self.draw_unknown_fields(ui);
+
             ui.heading("Also known as:");
             self.also_known_as.ui(ui);
+            if let Some(did) = self.handles.ui(ui) {
+                if let Some(unsigned_op) = self.try_get_unsigned_plc_op_print_errors() {
+                    self.handles.start(unsigned_op, did);
+                }
+            }
 
             ui.heading("Rotation keys:");
             self.rotation_keys.ui(ui, key_store);
@@ -66,6 +90,14 @@ impl PlcBuilderInterface {
             ui.heading("Services:");
             self.services.ui(ui);
 
+            ui.collapsing("Labeler preset", |ui| {
+                if let Some((endpoint, label_key)) = self.labeler.ui(ui, key_store) {
+                    if let Err(err) = self.apply_labeler(&endpoint, label_key) {
+                        error!("Failed to add labeler: {err}");
+                    }
+                }
+            });
+
             ui.heading("Previous CID:");
             {
                 let text = {
@@ -87,6 +119,10 @@ impl PlcBuilderInterface {
                     match plc_op.get_cid_reference() {
                         Ok(prev) => {
                             self.prev = Some(prev);
+                            if plc_op.is_genesis() {
+                                self.handles.set_did(&plc_op.get_did_plc());
+                            }
+                            self.prev_op = Some(plc_op);
                         }
                         Err(err) => {
                             error!("Failed to get CID to PLC operation: {err}");
@@ -95,6 +131,13 @@ impl PlcBuilderInterface {
                 }
             });
 
+            if let Some(prev_op) = &self.prev_op {
+                ui.heading("Changes:");
+                self.draw_changes(ui, prev_op);
+            }
+
+            self.draw_lints(ui, key_store);
+
             ui.add_space(20.0);
 
             ui.group(|ui| self.draw_action_column(ui, key_store));
@@ -122,9 +165,21 @@ impl PlcBuilderInterface {
         }
     }
 
-    pub fn with_atproto_pds(mut self, pds_endpoint: String) -> Self {
-        self.services.add_atproto_pds(pds_endpoint);
-        self
+    pub fn with_atproto_pds(mut self, pds_endpoint: &str) -> Result<Self> {
+        self.services.add_atproto_pds(pds_endpoint)?;
+        Ok(self)
+    }
+
+    /// Sets the labeler service & label key (see [PlcOperationBuilder::set_labeler]),
+    /// which requires the rest of the operation to be valid
+    fn apply_labeler(&mut self, endpoint: &str, label_key: DidKey) -> Result<()> {
+        let plc_op = PlcOperationBuilder::edit(&self.get_unsigned_plc_op()?)
+            .set_labeler(endpoint, label_key)?
+            .build()?;
+        self.services = ServicesInterface::from_map(plc_op.services().clone());
+        self.verification_methods =
+            VerificationMethodsInterface::from_map(plc_op.verification_methods().clone());
+        Ok(())
     }
 
     fn get_unsigned_plc_op(&self) -> Result<UnsignedPlcOperation> {
@@ -134,7 +189,7 @@ impl PlcBuilderInterface {
             self.also_known_as
                 .get_aka_uris()
                 .context("Failed to parse AkaUris")?,
-            HashMap::from_iter(
+            IndexMap::from_iter(
                 self.services
                     .get_map()
                     .iter()
@@ -147,7 +202,74 @@ impl PlcBuilderInterface {
                     .collect::<Result<Vec<_>>>()?,
             ),
             self.prev,
-        )?)
+        )?
+        .with_unknown_fields(self.unknown_fields.clone()))
+    }
+
+    /// Lists the unknown fields of the loaded operation, which must be dropped before signing
+    fn draw_unknown_fields(&mut self, ui: &mut Ui) {
+        if self.unknown_fields.is_empty() {
+            return;
+        }
+
+        ui.heading("Unknown fields:");
+        for (field, value) in &self.unknown_fields {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("{field}: {value}"));
+        }
+        if ui
+            .button("Drop unknown fields (plc.directory rejects them)")
+            .clicked()
+        {
+            self.unknown_fields.clear();
+        }
+    }
+
+    /// Lists the changes relative to the previous operation
+    fn draw_changes(&self, ui: &mut Ui, prev_op: &SignedPlcOperation) {
+        let Ok(plc_op) = self.get_unsigned_plc_op() else {
+            ui.label(RichText::new("[invalid operation]").weak().italics());
+            return;
+        };
+        let changes = prev_op.diff(&plc_op);
+        if changes.is_empty() {
+            ui.label(RichText::new("[none]").weak().italics());
+        }
+        for change in changes {
+            ui.monospace(change.to_string());
+        }
+    }
+
+    /// Lists the footguns of the current operation, if there are any
+    fn draw_lints(&self, ui: &mut Ui, key_store: &KeyStore) {
+        let Ok(plc_op) = self.get_unsigned_plc_op() else {
+            return;
+        };
+        let lints = self.lint(&plc_op, key_store);
+        let handle_warning = self.handles.primary_handle_warning(&plc_op);
+        if lints.is_empty() && handle_warning.is_none() {
+            return;
+        }
+
+        ui.heading("Warnings:");
+        for lint in lints {
+            ui.colored_label(ui.visuals().warn_fg_color, lint.to_string());
+        }
+        if let Some(warning) = handle_warning {
+            ui.colored_label(ui.visuals().warn_fg_color, warning);
+        }
+    }
+
+    fn lint(&self, plc_op: &UnsignedPlcOperation, key_store: &KeyStore) -> Vec<OperationLint> {
+        let owned_keys: Vec<_> = key_store
+            .keys()
+            .iter()
+            .map(|key| key.as_did_key())
+            .collect();
+        let context = LintContext {
+            owned_keys: &owned_keys,
+            handle: self.handles.expected_handle(),
+        };
+        plc_op.lint(self.prev_op.as_ref(), &context)
     }
 
     fn draw_action_column(&mut self, ui: &mut Ui, key_store: &KeyStore) {
@@ -158,6 +280,9 @@ impl PlcBuilderInterface {
                     let json = serde_json::ser::to_string_pretty(&plc_op)
                         .unwrap_or("Failed to serialize plc operation".to_string());
                     println!("{json}");
+                    for violation in plc_op.validate_for_signing() {
+                        warn!("{violation}");
+                    }
                 }
                 Err(err) => {
                     for err in err.chain().take(3) {
@@ -168,52 +293,108 @@ impl PlcBuilderInterface {
         }
 
         if ui.button("Sign & print JSON").clicked() {
-            let key = self.rotation_keys.try_get_selected_key();
-            let Some(key) = key else {
-                error!("No key selected");
+            let Some(signing_key) = self.try_get_signing_key(key_store) else {
                 return;
             };
-            if !self.rotation_keys.contains(key) {
-                error!("Selected key is not in rotation keys");
-                return;
-            }
-            let Some(signing_key) = key_store.try_get_by_did_key(key) else {
-                error!("Selected key is not in key store");
+            let Some(unsigned_op) = self.try_get_unsigned_plc_op_print_errors() else {
                 return;
             };
-            let unsigned_op = match self.get_unsigned_plc_op() {
-                Ok(plc_op) => plc_op,
-                Err(err) => {
-                    error!("Error getting PLC operation:");
+            for lint in self.lint(&unsigned_op, key_store) {
+                warn!("{lint}");
+            }
+            if let Some(warning) = self.handles.primary_handle_warning(&unsigned_op) {
+                warn!("{warning}");
+            }
 
-                    for err in err.chain().take(3) {
-                        error!("{}", err);
+            match signing_key.sign_plc_op(unsigned_op) {
+                Ok(signed_op) => Self::print_signed_plc_op(&signed_op),
+                Err(err) => {
+                    error!("Refusing to sign, the operation would be rejected by plc.directory:");
+                    for violation in err.0 {
+                        error!("{violation}");
                     }
-                    return;
                 }
             };
+        }
 
-            let signed_op = signing_key.sign_plc_op(unsigned_op);
+        ui.label("Vanity DID (genesis operations only):");
+        if let Some(prefix) = self.vanity.ui(ui) {
+            if let (Some(signing_key), Some(unsigned_op)) = (
+                self.try_get_signing_key(key_store),
+                self.try_get_unsigned_plc_op_print_errors(),
+            ) {
+                info!(
+                    "Searching for did:plc:{}, expecting ~{:.0} signatures",
+                    prefix.as_str(),
+                    prefix.expected_attempts()
+                );
+                self.vanity.start(unsigned_op, signing_key.clone(), prefix);
+            }
+        }
+        match self.vanity.take_result() {
+            Some(Some(signed_op)) => Self::print_signed_plc_op(&signed_op),
+            Some(None) => info!("Vanity DID search stopped"),
+            None => {}
+        }
+    }
 
-            let result = match serde_json::ser::to_string_pretty(&signed_op) {
-                Ok(res) => res,
-                Err(err) => {
-                    error!("Error serializing signed PLC operation: {err}");
-                    return;
+    /// The key store key of the selected rotation key, errors are logged
+    fn try_get_signing_key<'a>(
+        &self,
+        key_store: &'a KeyStore,
+    ) -> Option<&'a PlcBlessedSigningKeyBox> {
+        let Some(key) = self.rotation_keys.try_get_selected_key() else {
+            error!("No key selected");
+            return None;
+        };
+        if !self.rotation_keys.contains(key) {
+            error!("Selected key is not in rotation keys");
+            return None;
+        }
+        let signing_key = key_store.try_get_by_did_key(key);
+        if signing_key.is_none() {
+            error!("Selected key is not in key store");
+        }
+        signing_key
+    }
+
+    fn try_get_unsigned_plc_op_print_errors(&self) -> Option<UnsignedPlcOperation> {
+        match self.get_unsigned_plc_op() {
+            Ok(plc_op) => Some(plc_op),
+            Err(err) => {
+                error!("Error getting PLC operation:");
+
+                for err in err.chain().take(3) {
+                    error!("{}", err);
                 }
-            };
+                None
+            }
+        }
+    }
 
-            println!("Signed PLC operation:\n{result}");
-            println!("Identifier: {}", signed_op.get_did_plc());
-            if signed_op.prev().is_some() {
-                println!("(Note: this is not a genesis operation! You may need the genesis did:plc instead.)");
+    fn print_signed_plc_op(signed_op: &SignedPlcOperation) {
+        let result = match serde_json::ser::to_string_pretty(signed_op) {
+            Ok(res) => res,
+            Err(err) => {
+                error!("Error serializing signed PLC operation: {err}");
+                return;
             }
+        };
+
+        println!("Signed PLC operation:\n{result}");
+        println!("Identifier: {}", signed_op.get_did_plc());
+        if signed_op.prev().is_some() {
+            println!("(Note: this is not a genesis operation! You may need the genesis did:plc instead.)");
         }
     }
 
     fn from_signed_plc_op_with_ref(plc_op: SignedPlcOperation) -> Result<Self> {
         let mut new = Self::from_unsigned_plc_op_direct((*plc_op).clone())?;
         new.prev = Some(PlcOperationRef::from_signed_op(&plc_op)?);
+        if plc_op.is_genesis() {
+            new.handles.set_did(&plc_op.get_did_plc());
+        }
+        new.prev_op = Some(plc_op);
         Ok(new)
     }
 
@@ -236,12 +417,18 @@ impl PlcBuilderInterface {
             verification_methods,
             services,
             prev,
-            ..Default::default()
+            prev_op: None,
+            unknown_fields: plc_op.unknown_fields().clone(),
+            vanity: Default::default(),
+            handles: Default::default(),
+            labeler: Default::default(),
         })
     }
 
     pub fn new_with_defaults() -> Self {
-        PlcBuilderInterface::default().with_atproto_pds("https://pds.invalid".to_owned())
+        PlcBuilderInterface::default()
+            .with_atproto_pds("https://pds.invalid")
+            .expect("Invalid default PDS endpoint")
     }
 }
 
@@ -250,6 +437,10 @@ impl PlcBuilderInterface {
 /// Returns `Some(Result)` when the user attempts to parse a PLC operation.
 /// - `Result::Ok(SignedPlcOperation)` if parsing was successful.
 /// - `Result::Err` with an `anyhow` error if there was an error while parsing.
+///
+/// Unknown fields are kept (so the CID stays correct), and carried over into the builder
+/// when the operation is loaded there. They are logged as a warning, since plc.directory
+/// rejects them in new operations (the builder offers to drop them).
 fn plc_json_loader_ui(ui: &mut Ui, button_text: &str) -> Option<Result<SignedPlcOperation>> {
     let button = egui::Button::new(button_text);
     let btn_resp = button.ui(ui);
@@ -278,5 +469,20 @@ fn plc_json_loader_ui(ui: &mut Ui, button_text: &str) -> Option<Result<SignedPlc
     }
 
     btn_resp.surrender_focus();
-    Some(serde_json::de::from_str(&clipboard).context("Failed to deserialize JSON in clipboard"))
+    let plc_op = SignedPlcOperation::from_json(&clipboard, ParseMode::Lenient)
+        .context("Failed to deserialize JSON in clipboard");
+    if let Ok(plc_op) = &plc_op {
+        let unknown_fields = plc_op.unknown_fields();
+        if !unknown_fields.is_empty() {
+            let fields: Vec<_> = unknown_fields
+                .keys()
+                .map(|field| format!("`{field}`"))
+                .collect();
+            warn!(
+                "Operation has unknown fields ({}), which plc.directory rejects in new operations",
+                fields.join(", ")
+            );
+        }
+    }
+    Some(plc_op)
 }
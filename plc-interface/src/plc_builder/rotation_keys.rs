@@ -29,7 +29,7 @@ impl RotationKeySetInterface {
 
             enum RotKey {
                 Invalid,
-                NotOwned(DidKey),
+                NotOwned,
                 Owned(DidKey),
             }
 
@@ -40,7 +40,7 @@ impl RotationKeySetInterface {
                             if loaded_keys.contains(&key) {
                                 RotKey::Owned(key)
                             } else {
-                                RotKey::NotOwned(key)
+                                RotKey::NotOwned
                             }
                         } else {
                             RotKey::Invalid
@@ -56,7 +56,7 @@ impl RotationKeySetInterface {
                     let mut key_field = TextEdit::singleline(rot_key_str);
                     key_field = match &rot_key {
                         RotKey::Invalid => key_field.text_color(Color32::DARK_RED),
-                        RotKey::NotOwned(_) => key_field.text_color(Color32::LIGHT_GRAY),
+                        RotKey::NotOwned => key_field.text_color(Color32::LIGHT_GRAY),
                         RotKey::Owned(_) => key_field.text_color(Color32::DARK_GREEN),
                     };
 
@@ -69,8 +69,8 @@ impl RotationKeySetInterface {
     pub fn try_get_keys(&self) -> Result<Vec<DidKey>> {
         self.rotation_keys
             .iter()
-            .cloned()
             .filter(|k| !k.is_empty())
+            .cloned()
             .map(DidKey::try_from)
             .map(|res| res.context("Failed to parse did:key"))
             .collect::<Result<Vec<_>>>()
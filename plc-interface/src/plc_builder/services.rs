@@ -1,56 +1,116 @@
-use std::collections::HashMap;
-
-use did_plc::PlcService;
-use egui::{RichText, Ui, Widget};
+use anyhow::{bail, Context, Result};
+use did_plc::{PlcService, ServiceKind, ATPROTO_PDS_SERVICE_ID};
+use egui::{RichText, TextBuffer, Ui, Widget};
+use indexmap::IndexMap;
+use log::error;
 
 use crate::app::AppSection;
 use crate::ui_helpers::hash_map::HashMapRenderer;
 
-#[derive(Clone, Debug)]
+#[derive(Default, Clone, Debug)]
 pub struct ServicesInterface {
     services: HashMapRenderer<String, PlcServiceInterface>,
+    input_fields: InputFields,
+}
+
+#[derive(Default, Clone, Debug)]
+struct InputFields {
+    id: String,
+    r#type: String,
+    endpoint: String,
 }
 
-impl Default for ServicesInterface {
-    fn default() -> Self {
-        let mut services: HashMapRenderer<_, _> = Default::default();
-        services.allow_remove = false;
-        Self { services }
+impl InputFields {
+    fn try_get_service(&mut self) -> Result<(String, PlcService)> {
+        if self.id.is_empty() {
+            bail!("Service ID is empty")
+        }
+        if self.r#type.is_empty() {
+            bail!("Service type is empty")
+        }
+        let service = PlcService::new(ServiceKind::from(self.r#type.as_str()), &self.endpoint)?;
+        self.endpoint.clear();
+        Ok((self.id.take(), service))
     }
 }
 
 impl ServicesInterface {
     pub fn ui(&mut self, ui: &mut Ui) {
         self.services.ui(ui);
+        self.draw_input_field(ui);
     }
 
-    pub fn add_atproto_pds(&mut self, pds_endpoint: String) {
-        self.services.insert(
-            "atproto_pds".to_string(),
-            PlcService::new_atproto_pds(pds_endpoint).into(),
+    pub fn add_atproto_pds(&mut self, pds_endpoint: &str) -> Result<()> {
+        self.insert(
+            ATPROTO_PDS_SERVICE_ID.to_string(),
+            PlcService::new_atproto_pds(pds_endpoint).context("Invalid PDS endpoint")?,
         );
+        Ok(())
     }
 
-    pub fn get_map(&self) -> &HashMap<String, PlcServiceInterface> {
+    /// Adds (or replaces) a service
+    pub fn insert(&mut self, id: String, service: PlcService) {
+        self.services.insert(id, service.into());
+    }
+
+    fn draw_input_field(&mut self, ui: &mut Ui) {
+        ui.vertical(|ui| {
+            ui.group(|ui| {
+                egui::Grid::new("Service input fields").show(ui, |ui| {
+                    ui.label("ID:");
+                    ui.text_edit_singleline(&mut self.input_fields.id);
+                    ui.end_row();
+                    ui.label("Type:");
+                    egui::ComboBox::from_id_salt("Service type selector")
+                        .selected_text(&self.input_fields.r#type)
+                        .show_ui(ui, |ui| {
+                            for kind in [
+                                ServiceKind::AtprotoPersonalDataServer,
+                                ServiceKind::AtprotoLabeler,
+                                ServiceKind::BskyFeedGenerator,
+                            ] {
+                                ui.selectable_value(
+                                    &mut self.input_fields.r#type,
+                                    kind.to_string(),
+                                    kind.as_str(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Endpoint:");
+                    ui.text_edit_singleline(&mut self.input_fields.endpoint);
+                    ui.end_row();
+                });
+            });
+            if ui.button("Add").clicked() {
+                match self.input_fields.try_get_service() {
+                    Ok((id, service)) => self.insert(id, service),
+                    Err(err) => error!("{err}"),
+                }
+            }
+        });
+    }
+
+    pub fn get_map(&self) -> &IndexMap<String, PlcServiceInterface> {
         self.services.inner()
     }
 
-    pub fn from_map(map: HashMap<String, PlcService>) -> Self {
-        let map = HashMap::from_iter(map.into_iter().map(|(k, v)| (k, v.into())));
+    pub fn from_map(map: IndexMap<String, PlcService>) -> Self {
+        let map = IndexMap::from_iter(map.into_iter().map(|(k, v)| (k, v.into())));
 
         let mut map_renderer = HashMapRenderer::default();
         *map_renderer.inner_mut() = map;
-        map_renderer.allow_remove = false; // TODO: make true once an editor is implemented
 
         Self {
             services: map_renderer,
+            input_fields: InputFields::default(),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct PlcServiceInterface {
-    r#type: String,
+    r#type: ServiceKind,
     endpoint_buffer: String,
 }
 
@@ -67,22 +127,24 @@ impl TryInto<PlcService> for PlcServiceInterface {
     type Error = anyhow::Error;
 
     fn try_into(self) -> Result<PlcService, Self::Error> {
-        Ok(PlcService {
-            r#type: self.r#type,
-            endpoint: self.endpoint_buffer,
-        })
+        PlcService::new(self.r#type.clone(), &self.endpoint_buffer)
+            .with_context(|| format!("Invalid {} endpoint", self.r#type))
     }
 }
 
 impl AppSection for PlcServiceInterface {
     fn draw_and_update(&mut self, ui: &mut Ui) {
         ui.vertical(|ui| {
-            ui.label(RichText::new(&self.r#type).italics().weak());
+            ui.label(RichText::new(self.r#type.as_str()).italics().weak());
 
             egui::TextEdit::singleline(&mut self.endpoint_buffer)
                 .frame(true)
                 .interactive(true)
                 .ui(ui);
+
+            if let Err(err) = PlcService::new(self.r#type.clone(), &self.endpoint_buffer) {
+                ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+            }
         });
     }
 }
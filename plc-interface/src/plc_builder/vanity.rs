@@ -0,0 +1,127 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use did_plc::{
+    find_vanity_did, PlcBlessedSigningKeyBox, SignedPlcOperation, UnsignedPlcOperation,
+    VanityError, VanityPrefix, VanityProgress,
+};
+use egui::{ProgressBar, Ui};
+use log::error;
+
+type SearchResult = Result<Option<SignedPlcOperation>, VanityError>;
+
+#[derive(Debug, Default, Clone)]
+pub struct VanityInterface {
+    prefix: String,
+    search: Option<VanitySearch>,
+}
+
+/// A search running in the background
+#[derive(Debug, Clone)]
+struct VanitySearch {
+    prefix: VanityPrefix,
+    progress: Arc<VanityProgress>,
+    cancel: Arc<AtomicBool>,
+    result: Arc<OnceLock<SearchResult>>,
+}
+
+impl VanityInterface {
+    /// Draws the prefix field & search progress.
+    ///
+    /// Returns the prefix when the user starts a search (see [Self::start]).
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<VanityPrefix> {
+        let Some(search) = &self.search else {
+            let mut start = None;
+            ui.horizontal(|ui| {
+                ui.label("did:plc:");
+                ui.text_edit_singleline(&mut self.prefix);
+                if ui.button("Sign with vanity DID prefix").clicked() {
+                    match VanityPrefix::new(&self.prefix) {
+                        Ok(prefix) => start = Some(prefix),
+                        Err(err) => error!("{err}"),
+                    }
+                }
+            });
+            return start;
+        };
+
+        let progress = &search.progress;
+        ui.label(format!(
+            "Searching for did:plc:{}... ({} signatures, {:.0}/s)",
+            search.prefix.as_str(),
+            progress.attempts(),
+            progress.attempts_per_second(),
+        ));
+        let eta = match progress.expected_remaining() {
+            Some(eta) => format_duration(eta),
+            None => "unknown".to_string(),
+        };
+        ui.add(
+            ProgressBar::new(progress.success_probability() as f32)
+                .text(format!("Expected time: {eta}")),
+        );
+        if ui.button("Cancel").clicked() {
+            search.cancel.store(true, Ordering::Relaxed);
+        }
+
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+        None
+    }
+
+    /// Starts searching in the background, on all available cores.
+    pub fn start(
+        &mut self,
+        unsigned_op: UnsignedPlcOperation,
+        signing_key: PlcBlessedSigningKeyBox,
+        prefix: VanityPrefix,
+    ) {
+        let search = VanitySearch {
+            progress: Arc::new(VanityProgress::new(&prefix)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            result: Arc::new(OnceLock::new()),
+            prefix,
+        };
+        let threads = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+        let background = search.clone();
+        thread::spawn(move || {
+            let result = find_vanity_did(
+                &unsigned_op,
+                &*signing_key,
+                &background.prefix,
+                threads,
+                &background.progress,
+                &background.cancel,
+            );
+            let _ = background.result.set(result);
+        });
+        self.search = Some(search);
+    }
+
+    /// The signed operation of a finished search (`None` if it failed or was cancelled),
+    /// returned once. Errors are logged.
+    pub fn take_result(&mut self) -> Option<Option<SignedPlcOperation>> {
+        let result = match self.search.as_ref()?.result.get()? {
+            Ok(signed_op) => signed_op.clone(),
+            Err(err) => {
+                error!("Vanity DID search failed: {err}");
+                None
+            }
+        };
+        self.search = None;
+        Some(result)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
@@ -1,8 +1,7 @@
-use std::collections::HashMap;
-
 use anyhow::{bail, Result};
 use did_key::DidKey;
 use egui::{TextBuffer, Ui};
+use indexmap::IndexMap;
 use log::error;
 
 use crate::ui_helpers::hash_map::HashMapRenderer;
@@ -35,7 +34,7 @@ impl VerificationMethodsInterface {
         self.draw_input_field(ui);
     }
 
-    pub fn get_map(&self) -> &HashMap<String, DidKey> {
+    pub fn get_map(&self) -> &IndexMap<String, DidKey> {
         self.map_renderer.inner()
     }
 
@@ -61,7 +60,7 @@ impl VerificationMethodsInterface {
         });
     }
 
-    pub fn from_map(map: HashMap<String, DidKey>) -> Self {
+    pub fn from_map(map: IndexMap<String, DidKey>) -> Self {
         let mut map_renderer = HashMapRenderer::default();
         *map_renderer.inner_mut() = map;
         Self {
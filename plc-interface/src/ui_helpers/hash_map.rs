@@ -1,18 +1,19 @@
-use std::collections::HashMap;
 use std::hash::Hash;
 
 use derive_more::{Deref, DerefMut, Into};
 use did_key::DidKey;
 use egui::{Button, RichText, Ui, Widget};
+use indexmap::IndexMap;
 
 use crate::app::AppSection;
 
+/// Renders the entries of a map in insertion order
 #[derive(Clone, Debug, Deref, DerefMut, Into)]
 pub struct HashMapRenderer<K, V> {
     #[deref]
     #[deref_mut]
     #[into]
-    map: HashMap<K, V>,
+    map: IndexMap<K, V>,
     pub allow_remove: bool,
 }
 
@@ -32,14 +33,14 @@ impl<K, V> Default for HashMapRenderer<K, V> {
 // }
 
 impl<K, V> HashMapRenderer<K, V> {
-    pub fn inner(&self) -> &HashMap<K, V> {
+    pub fn inner(&self) -> &IndexMap<K, V> {
         &self.map
     }
-    pub fn inner_mut(&mut self) -> &mut HashMap<K, V> {
+    pub fn inner_mut(&mut self) -> &mut IndexMap<K, V> {
         &mut self.map
     }
 
-    pub fn into_inner(self) -> HashMap<K, V> {
+    pub fn into_inner(self) -> IndexMap<K, V> {
         self.map
     }
 }
@@ -53,7 +54,7 @@ where
         Self::draw_map_items(&mut self.map, self.allow_remove, ui);
     }
 
-    fn draw_map_items(map: &mut HashMap<K, V>, allow_removing: bool, ui: &mut Ui) {
+    fn draw_map_items(map: &mut IndexMap<K, V>, allow_removing: bool, ui: &mut Ui) {
         ui.group(|ui| {
             ui.vertical(|ui| {
                 if map.is_empty() {
@@ -70,7 +71,7 @@ where
                     }
 
                     if let Some(name) = key_to_remove {
-                        map.remove(&name);
+                        map.shift_remove(&name);
                     }
                 }
             })
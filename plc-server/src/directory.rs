@@ -0,0 +1,263 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use did_plc::{
+    AuditLog, AuditLogEntry, DidPlc, InvalidOperation, Resolution, ResolutionError,
+    SignedOperation, UnsignedOperation,
+};
+use thiserror::Error;
+
+use crate::storage::{self, Storage};
+
+/// A did:plc directory: validates submitted operations and keeps the audit logs.
+pub struct Directory<S> {
+    // Writes are serialized, so that each submission sees the result of the previous one
+    storage: RwLock<S>,
+}
+
+#[derive(Error, Debug)]
+pub enum SubmitError {
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
+    #[error(transparent)]
+    InvalidOperation(#[from] InvalidOperation),
+    #[error("Legacy `create` operations are no longer accepted")]
+    LegacyCreate,
+    #[error("Genesis operation hashes to `{actual}`, not `{expected}`")]
+    DidMismatch { expected: DidPlc, actual: DidPlc },
+    #[error("DID not registered: {0}")]
+    NotRegistered(DidPlc),
+    #[error("Operation rejected: {0}")]
+    Rejected(#[from] ResolutionError),
+}
+
+impl<S: Storage> Directory<S> {
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage: RwLock::new(storage),
+        }
+    }
+
+    /// The audit log of `did`, or `None` if the DID is not registered.
+    pub fn audit_log(&self, did: &DidPlc) -> Result<Option<AuditLog>, storage::Error> {
+        self.storage
+            .read()
+            .expect("Storage lock poisoned")
+            .audit_log(did)
+    }
+
+    /// Up to `count` entries (of all DIDs) created after `after`, ordered by creation time.
+    pub fn export(
+        &self,
+        after: Option<DateTime<Utc>>,
+        count: usize,
+    ) -> Result<Vec<AuditLogEntry>, storage::Error> {
+        self.storage
+            .read()
+            .expect("Storage lock poisoned")
+            .export(after, count)
+    }
+
+    /// Validates an operation submitted for `did` at time `now`, and stores it if accepted.
+    ///
+    /// On top of the audit log rules (see [AuditLog::resolve]), the operation must satisfy
+    /// the plc.directory constraints, a genesis operation must hash to `did`,
+    /// and legacy `create` operations are refused.
+    pub fn submit(
+        &self,
+        did: &DidPlc,
+        operation: SignedOperation,
+        now: DateTime<Utc>,
+    ) -> Result<Resolution, SubmitError> {
+        let violations = operation.validate();
+        if !violations.is_empty() {
+            return Err(InvalidOperation(violations).into());
+        }
+        if matches!(operation.unsigned(), UnsignedOperation::LegacyCreate(_)) {
+            return Err(SubmitError::LegacyCreate);
+        }
+
+        let mut storage = self.storage.write().expect("Storage lock poisoned");
+        let audit_log = storage.audit_log(did)?;
+
+        let mut audit_log = if operation.is_genesis() {
+            let actual = operation.get_did_plc();
+            if actual != *did {
+                return Err(SubmitError::DidMismatch {
+                    expected: did.clone(),
+                    actual,
+                });
+            }
+            audit_log.unwrap_or_default()
+        } else {
+            audit_log.ok_or_else(|| SubmitError::NotRegistered(did.clone()))?
+        };
+
+        let resolution = audit_log.apply(did, operation, now)?;
+        storage.put_audit_log(did, &audit_log)?;
+        Ok(resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use chrono::TimeDelta;
+    use did_key::DidKey;
+    use did_plc::{
+        PlcOperationRef, PlcService, ServiceKind, SignedPlcOperation, UnsignedPlcOperation,
+    };
+    use ecdsa::SigningKey;
+    use indexmap::IndexMap;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn did_key(key: &SigningKey<Secp256k1>) -> DidKey {
+        elliptic_curve::PublicKey::from(key.verifying_key()).into()
+    }
+
+    fn signed_op(
+        rotation_keys: &[DidKey],
+        prev: Option<PlcOperationRef>,
+        signing_key: &SigningKey<Secp256k1>,
+    ) -> SignedPlcOperation {
+        UnsignedPlcOperation::new(
+            rotation_keys.to_vec(),
+            IndexMap::new(),
+            vec![],
+            IndexMap::new(),
+            prev,
+        )
+        .unwrap()
+        .sign(signing_key)
+        .unwrap()
+    }
+
+    struct Fixture {
+        directory: Directory<MemoryStorage>,
+        did: DidPlc,
+        genesis_ref: PlcOperationRef,
+        keys: [SigningKey<Secp256k1>; 2],
+        created_at: DateTime<Utc>,
+    }
+
+    /// A directory with a single DID, with rotation keys `[a, b]`
+    fn fixture() -> Fixture {
+        let keys = [(); 2].map(|_| SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng));
+        let rotation_keys: Vec<_> = keys.iter().map(did_key).collect();
+        let created_at = Utc::now();
+
+        let genesis = signed_op(&rotation_keys, None, &keys[0]);
+        let did = genesis.get_did_plc();
+        let genesis_ref = genesis.get_cid_reference().unwrap();
+
+        let directory = Directory::new(MemoryStorage::default());
+        directory.submit(&did, genesis.into(), created_at).unwrap();
+
+        Fixture {
+            directory,
+            did,
+            genesis_ref,
+            keys,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn accept_update() {
+        let f = fixture();
+        let update = signed_op(&[did_key(&f.keys[1])], Some(f.genesis_ref), &f.keys[1]);
+
+        f.directory
+            .submit(&f.did, update.into(), f.created_at)
+            .unwrap();
+        assert_eq!(f.directory.audit_log(&f.did).unwrap().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn accept_local_pds_endpoint() {
+        let f = fixture();
+        // Refused when signing with this crate, but plc.directory accepts it
+        let local_pds = PlcService {
+            r#type: ServiceKind::AtprotoPersonalDataServer,
+            endpoint: "http://localhost:2583".to_string(),
+        };
+        let update = UnsignedPlcOperation::new(
+            vec![did_key(&f.keys[1])],
+            IndexMap::new(),
+            vec![],
+            IndexMap::from([("atproto_pds".to_string(), local_pds)]),
+            Some(f.genesis_ref),
+        )
+        .unwrap()
+        .sign_unchecked(&f.keys[1]);
+
+        f.directory
+            .submit(&f.did, update.into(), f.created_at)
+            .unwrap();
+    }
+
+    #[test]
+    fn reject_did_mismatch() {
+        let f = fixture();
+        let other_genesis = signed_op(&[did_key(&f.keys[0])], None, &f.keys[0]);
+
+        assert_matches!(
+            f.directory
+                .submit(&f.did, other_genesis.into(), f.created_at),
+            Err(SubmitError::DidMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn reject_unregistered() {
+        let f = fixture();
+        let other_did = DidPlc::try_from("did:plc:c6te24qg5hx54qgegqylpqkx").unwrap();
+        let update = signed_op(&[did_key(&f.keys[0])], Some(f.genesis_ref), &f.keys[0]);
+
+        assert_matches!(
+            f.directory.submit(&other_did, update.into(), f.created_at),
+            Err(SubmitError::NotRegistered(_))
+        );
+    }
+
+    #[test]
+    fn reject_unknown_key() {
+        let f = fixture();
+        let unknown_key = SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng);
+        let update = signed_op(&[did_key(&unknown_key)], Some(f.genesis_ref), &unknown_key);
+
+        assert_matches!(
+            f.directory.submit(&f.did, update.into(), f.created_at),
+            Err(SubmitError::Rejected(ResolutionError::InvalidSignature(_)))
+        );
+    }
+
+    #[test]
+    fn recovery_window() {
+        let f = fixture();
+        let update = signed_op(&[did_key(&f.keys[1])], Some(f.genesis_ref), &f.keys[1]);
+        f.directory
+            .submit(&f.did, update.into(), f.created_at)
+            .unwrap();
+
+        // Key `a` may override the update signed by `b`, but only within the recovery window
+        let recovery = signed_op(&[did_key(&f.keys[0])], Some(f.genesis_ref), &f.keys[0]);
+        let late = f.created_at + did_plc::RECOVERY_WINDOW + TimeDelta::seconds(1);
+        assert_matches!(
+            f.directory.submit(&f.did, recovery.clone().into(), late),
+            Err(SubmitError::Rejected(
+                ResolutionError::RecoveryWindowClosed { .. }
+            ))
+        );
+
+        let resolution = f
+            .directory
+            .submit(&f.did, recovery.into(), f.created_at + TimeDelta::hours(1))
+            .unwrap();
+        assert_eq!(resolution.nullified, vec![1]);
+    }
+}
@@ -0,0 +1,9 @@
+mod directory;
+mod routes;
+pub mod storage;
+
+pub use directory::{Directory, SubmitError};
+pub use routes::router;
+
+/// Port used by the reference PLC directory implementation
+pub const DEFAULT_PORT: u16 = 2582;
@@ -0,0 +1,73 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use plc_server::storage::{DiskStorage, MemoryStorage, Storage};
+use plc_server::{router, Directory, DEFAULT_PORT};
+
+const USAGE: &str = "Usage: plc-server [--bind <address:port>] [--storage <directory>]
+
+Serves the PLC directory API. Audit logs are kept in memory, unless a storage directory is set.";
+
+struct Args {
+    bind: SocketAddr,
+    storage: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        bind: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT)),
+        storage: None,
+    };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--bind" => {
+                let value = value()?;
+                args.bind = value
+                    .parse()
+                    .map_err(|err| format!("Invalid address `{value}`: {err}"))?;
+            }
+            "--storage" => args.storage = Some(value()?.into()),
+            _ => return Err(format!("Unknown argument `{arg}`")),
+        }
+    }
+    Ok(args)
+}
+
+async fn serve<S: Storage + 'static>(storage: S, bind: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    log::info!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(Arc::new(Directory::new(storage)))).await
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let result = match &args.storage {
+        Some(path) => match DiskStorage::open(path) {
+            Ok(storage) => serve(storage, args.bind).await,
+            Err(err) => {
+                log::error!("Failed to open storage at {}: {err}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => serve(MemoryStorage::default(), args.bind).await,
+    };
+
+    if let Err(err) = result {
+        log::error!("Server failed: {err}");
+        std::process::exit(1);
+    }
+}
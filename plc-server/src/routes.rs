@@ -0,0 +1,380 @@
+use std::sync::Arc;
+
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, SubsecRound, Utc};
+use did_plc::{AuditLog, AuditLogEntry, DidDocument, DidPlc, DocumentData, SignedOperation};
+use serde::{Deserialize, Serialize};
+
+use crate::directory::{Directory, SubmitError};
+use crate::storage::{self, Storage};
+
+/// Default & maximum entry count of `/export` pages (same as plc.directory)
+const EXPORT_DEFAULT_COUNT: usize = 10;
+const EXPORT_MAX_COUNT: usize = 1000;
+
+/// Routes of the [PLC directory API](https://web.plc.directory/api/redoc)
+pub fn router<S: Storage + 'static>(directory: Arc<Directory<S>>) -> Router {
+    Router::new()
+        .route("/export", get(export::<S>))
+        .route("/{did}", get(get_document::<S>).post(post_operation::<S>))
+        .route("/{did}/log", get(get_log::<S>))
+        .route("/{did}/log/audit", get(get_audit_log::<S>))
+        .route("/{did}/log/last", get(get_last_operation::<S>))
+        .route("/{did}/data", get(get_data::<S>))
+        .with_state(directory)
+}
+
+/// An error response, with the same `{"message": ...}` body as plc.directory
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorMessage<'a> {
+    message: &'a str,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(ErrorMessage {
+            message: &self.message,
+        });
+        (self.status, body).into_response()
+    }
+}
+
+impl From<storage::Error> for ApiError {
+    fn from(err: storage::Error) -> Self {
+        log::error!("{err}");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+    }
+}
+
+impl From<SubmitError> for ApiError {
+    fn from(err: SubmitError) -> Self {
+        match err {
+            SubmitError::Storage(err) => err.into(),
+            SubmitError::NotRegistered(_) => Self::new(StatusCode::NOT_FOUND, err.to_string()),
+            err => Self::new(StatusCode::BAD_REQUEST, err.to_string()),
+        }
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+type DirectoryState<S> = State<Arc<Directory<S>>>;
+
+fn parse_did(did: &str) -> ApiResult<DidPlc> {
+    DidPlc::try_from(did)
+        .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid DID: {err}")))
+}
+
+fn load_audit_log<S: Storage>(directory: &Directory<S>, did: &DidPlc) -> ApiResult<AuditLog> {
+    directory
+        .audit_log(did)?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("DID not registered: {did}")))
+}
+
+/// Runs `f` on tokio's blocking thread pool: the directory locks its storage, and
+/// [DiskStorage](crate::storage::DiskStorage) does synchronous file I/O, neither of which may
+/// stall the async workers.
+async fn with_directory<S, T>(
+    directory: Arc<Directory<S>>,
+    f: impl FnOnce(&Directory<S>) -> ApiResult<T> + Send + 'static,
+) -> ApiResult<T>
+where
+    S: Storage + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&directory))
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("Directory task failed: {err}");
+            Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error",
+            ))
+        })
+}
+
+/// The latest active entry, which must exist in a non-empty log
+fn last_active(audit_log: &AuditLog) -> ApiResult<&AuditLogEntry> {
+    audit_log.last_active().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Audit log has no active operations",
+        )
+    })
+}
+
+/// The current state, or `410 Gone` for a tombstoned DID
+fn current_state<S: Storage>(
+    directory: &Directory<S>,
+    did: &DidPlc,
+) -> ApiResult<did_plc::UnsignedPlcOperation> {
+    let audit_log = load_audit_log(directory, did)?;
+    match last_active(&audit_log)?.operation.normalized() {
+        Some(op) => Ok(op.clone()),
+        None => Err(ApiError::new(
+            StatusCode::GONE,
+            format!("DID not available: {did}"),
+        )),
+    }
+}
+
+async fn get_document<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+) -> ApiResult<Json<DidDocument>> {
+    let did = parse_did(&did)?;
+    let document = with_directory(directory, move |directory| {
+        let state = current_state(directory, &did)?;
+        Ok(DidDocument::new(&did, &state))
+    })
+    .await?;
+    Ok(Json(document))
+}
+
+async fn get_data<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+) -> ApiResult<Json<DocumentData>> {
+    let did = parse_did(&did)?;
+    let data = with_directory(directory, move |directory| {
+        let state = current_state(directory, &did)?;
+        Ok(DocumentData::new(&did, &state))
+    })
+    .await?;
+    Ok(Json(data))
+}
+
+async fn get_log<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+) -> ApiResult<Json<Vec<SignedOperation>>> {
+    let did = parse_did(&did)?;
+    let audit_log =
+        with_directory(directory, move |directory| load_audit_log(directory, &did)).await?;
+    let operations = audit_log
+        .active_entries()
+        .map(|(_, entry)| entry.operation.clone())
+        .collect();
+    Ok(Json(operations))
+}
+
+async fn get_audit_log<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+) -> ApiResult<Json<AuditLog>> {
+    let did = parse_did(&did)?;
+    let audit_log =
+        with_directory(directory, move |directory| load_audit_log(directory, &did)).await?;
+    Ok(Json(audit_log))
+}
+
+async fn get_last_operation<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+) -> ApiResult<Json<SignedOperation>> {
+    let did = parse_did(&did)?;
+    let audit_log =
+        with_directory(directory, move |directory| load_audit_log(directory, &did)).await?;
+    Ok(Json(last_active(&audit_log)?.operation.clone()))
+}
+
+async fn post_operation<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Path(did): Path<String>,
+    operation: Result<Json<SignedOperation>, JsonRejection>,
+) -> ApiResult<()> {
+    let did = parse_did(&did)?;
+    let Json(operation) = operation.map_err(|err| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid operation: {}", err.body_text()),
+        )
+    })?;
+
+    // plc.directory timestamps have millisecond precision
+    let now = Utc::now().trunc_subsecs(3);
+    let (did, resolution) = with_directory(directory, move |directory| {
+        let resolution = directory.submit(&did, operation, now)?;
+        Ok((did, resolution))
+    })
+    .await?;
+    if resolution.is_fork() {
+        log::info!(
+            "{did}: nullified {} operation(s)",
+            resolution.nullified.len()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    after: Option<DateTime<Utc>>,
+    count: Option<usize>,
+}
+
+/// `GET /export`, JSON lines of audit log entries (of all DIDs) ordered by creation time
+async fn export<S: Storage + 'static>(
+    State(directory): DirectoryState<S>,
+    Query(params): Query<ExportParams>,
+) -> ApiResult<Response> {
+    let count = params
+        .count
+        .unwrap_or(EXPORT_DEFAULT_COUNT)
+        .min(EXPORT_MAX_COUNT);
+    let entries = with_directory(directory, move |directory| {
+        Ok(directory.export(params.after, count)?)
+    })
+    .await?;
+
+    let lines = entries
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| ApiError::from(storage::Error::from(err)))?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/jsonlines")],
+        lines.join("\n"),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use std::{assert_matches, thread};
+
+    use did_plc::{
+        ClientError, ExportMirror, PlcClient, SignedPlcOperation, UnsignedPlcOperation,
+        UnsignedPlcTombstone,
+    };
+    use ecdsa::SigningKey;
+    use indexmap::IndexMap;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// Runs an in-memory directory on a random port, in the background
+    fn spawn_directory() -> PlcClient {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let directory = Arc::new(Directory::new(MemoryStorage::default()));
+                axum::serve(listener, router(directory)).await.unwrap();
+            });
+        });
+
+        PlcClient::new(format!("http://{address}").parse().unwrap()).unwrap()
+    }
+
+    fn genesis(key: &SigningKey<Secp256k1>) -> SignedPlcOperation {
+        let did_key: did_key::DidKey = elliptic_curve::PublicKey::from(key.verifying_key()).into();
+        UnsignedPlcOperation::new_genesis(
+            vec![did_key.clone()],
+            IndexMap::from([("atproto".to_string(), did_key)]),
+            vec![],
+            IndexMap::new(),
+        )
+        .unwrap()
+        .sign(key)
+        .unwrap()
+    }
+
+    #[test]
+    fn directory_roundtrip() {
+        let client = spawn_directory();
+        let key = SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng);
+        let genesis = genesis(&key);
+        let did = genesis.get_did_plc();
+
+        assert_matches!(
+            client.get_document(&did),
+            Err(ClientError::Status {
+                status: StatusCode::NOT_FOUND,
+                ..
+            })
+        );
+
+        client.submit_operation(&did, &genesis).unwrap();
+        let document = client.get_document(&did).unwrap();
+        assert_eq!(document.id, did.formatted_did());
+        assert_eq!(
+            client
+                .get_last_plc_operation(&did)
+                .unwrap()
+                .get_cid_reference()
+                .unwrap(),
+            genesis.get_cid_reference().unwrap()
+        );
+
+        let tombstone = UnsignedPlcTombstone::new(genesis.get_cid_reference().unwrap()).sign(&key);
+        client.submit_operation(&did, &tombstone).unwrap();
+        assert_matches!(
+            client.get_data(&did),
+            Err(ClientError::Status {
+                status: StatusCode::GONE,
+                ..
+            })
+        );
+        assert_eq!(client.get_audit_log(&did).unwrap().len(), 2);
+
+        // Resubmitting the genesis operation is rejected
+        assert_matches!(
+            client.submit_operation(&did, &genesis),
+            Err(ClientError::Status {
+                status: StatusCode::BAD_REQUEST,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn mirror_export() {
+        let client = spawn_directory();
+        let mut dids = Vec::new();
+        for _ in 0..3 {
+            let genesis = genesis(&SigningKey::random(&mut rand::rngs::OsRng));
+            dids.push(genesis.get_did_plc());
+            client
+                .submit_operation(&genesis.get_did_plc(), &genesis)
+                .unwrap();
+            // Pages are split by timestamps, which have millisecond precision
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut mirror = ExportMirror::open(dir.path()).unwrap();
+        assert_eq!(mirror.sync(&client, 2).unwrap(), 3);
+        assert!(dids
+            .iter()
+            .all(|did| mirror.audit_log(did).unwrap().is_some()));
+
+        // Already caught up
+        assert_eq!(mirror.sync(&client, 2).unwrap(), 0);
+    }
+}
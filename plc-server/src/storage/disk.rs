@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use did_plc::{AuditLog, AuditLogEntry, DidPlc};
+
+use crate::storage::{Error, ExportIndex, Storage};
+
+const LOG_EXTENSION: &str = "json";
+
+/// Stores each audit log as a JSON file in a directory.
+///
+/// Files are named after the DID hash (without the `did:plc:` prefix, since colons aren't
+/// valid in Windows file names), and are replaced atomically on every write.
+/// All entries are also indexed in memory, so that exports don't read every file.
+#[derive(Debug)]
+pub struct DiskStorage {
+    root: PathBuf,
+    export_index: ExportIndex,
+}
+
+impl DiskStorage {
+    /// Opens (or creates) the storage directory at `root`, and indexes the stored logs.
+    pub fn open(root: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(root)?;
+        let mut export_index = ExportIndex::default();
+        for dir_entry in fs::read_dir(root)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|ext| ext == LOG_EXTENSION) {
+                export_index.update(&Self::read_log(&path)?);
+            }
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            export_index,
+        })
+    }
+
+    fn log_path(&self, did: &DidPlc) -> PathBuf {
+        self.root
+            .join(did.hash_encoded())
+            .with_extension(LOG_EXTENSION)
+    }
+
+    fn read_log(path: &Path) -> Result<AuditLog, Error> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+impl Storage for DiskStorage {
+    fn audit_log(&self, did: &DidPlc) -> Result<Option<AuditLog>, Error> {
+        match Self::read_log(&self.log_path(did)) {
+            Ok(audit_log) => Ok(Some(audit_log)),
+            Err(Error::Io(err)) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn put_audit_log(&mut self, did: &DidPlc, audit_log: &AuditLog) -> Result<(), Error> {
+        let path = self.log_path(did);
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_vec(audit_log)?)?;
+        fs::rename(&temp_path, &path)?;
+        self.export_index.update(audit_log);
+        Ok(())
+    }
+
+    fn export(
+        &self,
+        after: Option<DateTime<Utc>>,
+        count: usize,
+    ) -> Result<Vec<AuditLogEntry>, Error> {
+        Ok(self.export_index.export(after, count))
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use did_plc::{AuditLog, AuditLogEntry, DidPlc};
+
+use crate::storage::{Error, ExportIndex, Storage};
+
+/// Keeps all audit logs in memory, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    audit_logs: HashMap<String, AuditLog>,
+    export_index: ExportIndex,
+}
+
+impl Storage for MemoryStorage {
+    fn audit_log(&self, did: &DidPlc) -> Result<Option<AuditLog>, Error> {
+        Ok(self.audit_logs.get(&did.formatted_did()).cloned())
+    }
+
+    fn put_audit_log(&mut self, did: &DidPlc, audit_log: &AuditLog) -> Result<(), Error> {
+        self.audit_logs
+            .insert(did.formatted_did(), audit_log.clone());
+        self.export_index.update(audit_log);
+        Ok(())
+    }
+
+    fn export(
+        &self,
+        after: Option<DateTime<Utc>>,
+        count: usize,
+    ) -> Result<Vec<AuditLogEntry>, Error> {
+        Ok(self.export_index.export(after, count))
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use chrono::{DateTime, Utc};
+use did_plc::{AuditLog, AuditLogEntry, DidPlc, PlcOperationRef};
+use thiserror::Error;
+
+mod disk;
+mod memory;
+
+pub use disk::DiskStorage;
+pub use memory::MemoryStorage;
+
+/// A backend storing the audit logs of all DIDs known to a directory.
+///
+/// The directory serializes writes, so implementations don't have to handle concurrent
+/// modifications of the same log.
+pub trait Storage: Send + Sync {
+    /// The audit log of `did`, or `None` if the DID is not registered.
+    fn audit_log(&self, did: &DidPlc) -> Result<Option<AuditLog>, Error>;
+
+    /// Replaces the audit log of `did`.
+    fn put_audit_log(&mut self, did: &DidPlc, audit_log: &AuditLog) -> Result<(), Error>;
+
+    /// Up to `count` entries (of all DIDs) created after `after`, ordered by creation time
+    /// (and CID, for entries created at the same time).
+    ///
+    /// Since the next page starts after the creation time of the last entry, pages only end
+    /// after all entries created at that time - so a page may be shorter than `count`, or
+    /// longer if more than `count` entries were created at the same time.
+    fn export(
+        &self,
+        after: Option<DateTime<Utc>>,
+        count: usize,
+    ) -> Result<Vec<AuditLogEntry>, Error>;
+}
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Storage I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Stored audit log is corrupted: {0}")]
+    Corrupted(#[from] serde_json::Error),
+}
+
+/// The entries of all audit logs, grouped by creation time, for [Storage::export]
+#[derive(Debug, Default)]
+struct ExportIndex(BTreeMap<DateTime<Utc>, BTreeMap<PlcOperationRef, AuditLogEntry>>);
+
+impl ExportIndex {
+    /// Adds the entries of `audit_log`, replacing the earlier versions of existing ones
+    /// (entries are only ever appended or nullified).
+    fn update(&mut self, audit_log: &AuditLog) {
+        for entry in audit_log.iter() {
+            self.0
+                .entry(entry.created_at)
+                .or_default()
+                .insert(entry.cid, entry.clone());
+        }
+    }
+
+    fn export(&self, after: Option<DateTime<Utc>>, count: usize) -> Vec<AuditLogEntry> {
+        let start = after.map_or(Bound::Unbounded, Bound::Excluded);
+        let mut entries = Vec::new();
+        for group in self
+            .0
+            .range((start, Bound::Unbounded))
+            .map(|(_, group)| group)
+        {
+            // A group larger than `count` is still returned, or the export would get stuck
+            if !entries.is_empty() && entries.len() + group.len() > count {
+                break;
+            }
+            entries.extend(group.values().cloned());
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use ecdsa::SigningKey;
+    use indexmap::IndexMap;
+    use k256::Secp256k1;
+
+    use super::*;
+    use crate::Directory;
+
+    /// Registers `count` DIDs, one second apart
+    fn fill<S: Storage>(directory: &Directory<S>, count: usize, start: DateTime<Utc>) {
+        for i in 0..count {
+            let key = SigningKey::<Secp256k1>::random(&mut rand::rngs::OsRng);
+            let did_key = elliptic_curve::PublicKey::from(key.verifying_key()).into();
+            let genesis = did_plc::UnsignedPlcOperation::new_genesis(
+                vec![did_key],
+                IndexMap::new(),
+                vec![],
+                IndexMap::new(),
+            )
+            .unwrap()
+            .sign(&key)
+            .unwrap();
+            let now = start + TimeDelta::seconds(i as i64);
+            directory
+                .submit(&genesis.get_did_plc(), genesis.into(), now)
+                .unwrap();
+        }
+    }
+
+    fn check_export<S: Storage>(storage: S) {
+        let directory = Directory::new(storage);
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        fill(&directory, 5, start);
+
+        let first_page = directory.export(None, 3).unwrap();
+        assert_eq!(first_page.len(), 3);
+        assert!(first_page.is_sorted_by_key(|entry| entry.created_at));
+
+        let cursor = first_page.last().unwrap().created_at;
+        let second_page = directory.export(Some(cursor), 3).unwrap();
+        assert_eq!(second_page.len(), 2);
+        assert!(second_page.iter().all(|entry| entry.created_at > cursor));
+
+        // 4 more DIDs, all created at the same time
+        let same_time = start + TimeDelta::minutes(1);
+        for _ in 0..4 {
+            fill(&directory, 1, same_time);
+        }
+        let cursor = second_page.last().unwrap().created_at;
+        let page = directory.export(Some(cursor), 3).unwrap();
+        assert_eq!(page.len(), 4);
+        assert!(page.iter().all(|entry| entry.created_at == same_time));
+        assert!(page.is_sorted_by_key(|entry| entry.cid));
+
+        // A page stops before an incomplete group of entries
+        fill(&directory, 1, start + TimeDelta::seconds(30));
+        let page = directory.export(Some(cursor), 3).unwrap();
+        assert_eq!(page.len(), 1);
+        assert!(directory.export(Some(same_time), 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn memory_export() {
+        check_export(MemoryStorage::default());
+    }
+
+    #[test]
+    fn disk_export() {
+        let dir = tempfile::tempdir().unwrap();
+        check_export(DiskStorage::open(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn disk_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = Utc::now();
+        fill(
+            &Directory::new(DiskStorage::open(dir.path()).unwrap()),
+            2,
+            start,
+        );
+
+        let reopened = Directory::new(DiskStorage::open(dir.path()).unwrap());
+        let entries = reopened.export(None, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(reopened.audit_log(&entries[0].did).unwrap().is_some());
+    }
+}